@@ -1,2 +1,4 @@
 pub mod history_downloader;
 pub mod history_reproducer;
+pub mod job_queue;
+pub mod streams;
@@ -1,148 +1,717 @@
-use crate::application::history_downloader::candlesticks_downloader_actor::CandlesticksDownloaderActor;
+use crate::application::history_downloader::candlesticks_downloader_actor::commands::backfill_candlesticks::BackfillCandlesticks;
+use crate::application::history_downloader::candlesticks_downloader_actor::commands::cancel_download::CancelDownload;
 use crate::application::history_downloader::candlesticks_downloader_actor::commands::download_candlesticks::DownloadCandlesticks;
+use crate::application::history_downloader::candlesticks_downloader_actor::commands::resume_job::ResumeJob;
+use crate::application::history_downloader::candlesticks_downloader_actor::errors::DownloadHistoryError;
+use crate::application::history_downloader::candlesticks_downloader_actor::plan::DownloadPlan;
+use crate::application::history_downloader::candlesticks_downloader_actor::progress::DownloadProgress;
+use crate::application::history_downloader::candlesticks_downloader_actor::queries::{GetLifecycleReceiver, GetProgressReceiver, PlanDownload};
+use crate::application::history_downloader::candlesticks_downloader_actor::{CandlesticksDownloaderActor, WindowPage};
+use crate::application::history_downloader::report::DownloadReport;
+use crate::application::job_queue::JobReceiver;
 use crate::config::AppConfig;
 use crate::integrations::clickhouse::ClickhouseRepositoryError;
-use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksRepository, CandlesticksWriteService};
-use crate::integrations::clickhouse_client;
-use actix::{Actor, Addr};
+use crate::integrations::clickhouse::candlesticks_repository::{
+    CandlesticksReadService, CandlesticksRepository, CandlesticksWriteService, DownloadCheckpointsService, JobId, LastOpenTimes, ListingDatesService,
+    WindowCheckpoints,
+};
+use crate::integrations::spool::SpooledWriteService;
+use actix::{Actor, Addr, Handler, MailboxError, Message};
 use candy_ass_core::application::actors::symbols_fetcher_actor;
-use candy_ass_core::application::actors::symbols_fetcher_actor::RefreshPolicy::OneShot;
-use candy_ass_core::application::actors::symbols_fetcher_actor::{GetReceiver, SymbolsFetcherActor};
-use candy_ass_core::domain::candlestick::Candlestick;
-use candy_ass_core::domain::symbol::{SymbolFilterFn, Symbols};
+use candy_ass_core::application::actors::symbols_fetcher_actor::RefreshPolicy;
+use candy_ass_core::application::actors::symbols_fetcher_actor::RefreshPolicy::{OneShot, Periodic};
+use candy_ass_core::application::actors::symbols_fetcher_actor::{GetReceiver, GetStatusReceiver, SymbolsFetcherActor, SymbolsStatus};
+use candy_ass_core::application::supervision::LifecycleEvent;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::domain::symbol::{Symbol, SymbolFilterFn, Symbols};
 use candy_ass_core::domain::timeframe::Timeframe;
 use candy_ass_core::integrations::binance_spot_client;
-use candy_ass_core::integrations::http::HttpResponseError;
 use candy_ass_core::integrations::http::binance::BINANCE_RATE_LIMIT;
+use candy_ass_core::integrations::http::binance::spot_http_client::{ExchangeInfoApi, KlinesApi};
+use futures_util::future::ready;
 use futures_util::stream::BoxStream;
-use futures_util::{StreamExt, TryFutureExt, TryStreamExt};
+use futures_util::{FutureExt, Stream, StreamExt, TryFutureExt, stream};
 use reqwest::Client;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use thiserror::Error;
 use time::OffsetDateTime;
-use tokio::sync::watch::error::SendError;
-use tokio_stream::wrappers::{ReceiverStream, WatchStream};
-use tracing::error;
+use tokio::sync::watch;
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_stream::wrappers::{BroadcastStream, IntervalStream, ReceiverStream, WatchStream};
+use tracing::{error, info};
 
 pub mod candlesticks_downloader_actor;
+pub mod report;
+
+/// Candlesticks page tagged with the job and the window it belongs to
+type JobCandlesticks = (JobId, WindowPage);
+
+/// Progress of every job a pipeline submitted, the pipeline reports all of them once it is drained
+type SubmittedJobs = Arc<Mutex<Vec<watch::Receiver<DownloadProgress>>>>;
+
+/// Parameters of a single [`Application::start_pipeline`] run
+#[derive(Clone)]
+pub struct DownloadRequest {
+    /// Timeframes to download, each with its own start date
+    pub timeframes: Vec<(Timeframe, OffsetDateTime)>,
+    /// Inclusive bound for `open_time`, `None` downloads up to the latest candlestick
+    pub end_date: Option<OffsetDateTime>,
+    pub filter: SymbolFilterFn,
+    pub mode: DownloadMode,
+}
+
+/// How the start of every symbol's download window is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadMode {
+    /// Every symbol starts at the timeframe start date
+    Full,
+    /// Every symbol continues from its last stored candlestick, symbols without data start at the timeframe start date
+    Resume,
+    /// Only the gaps between stored candlesticks since the timeframe start date are fetched
+    Backfill,
+}
+
+/// Parameters of [`Application::start_collector`], every timeframe is kept current on its own schedule
+#[derive(Clone)]
+pub struct CollectorSchedule {
+    pub timeframes: Vec<TimeframeSchedule>,
+    pub filter: SymbolFilterFn,
+    /// How often the clickhouse optimization runs, it never runs when `None`
+    pub optimization_interval: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeframeSchedule {
+    pub timeframe: Timeframe,
+    /// Start of symbols without stored candlesticks, e.g. new listings
+    pub start_date: OffsetDateTime,
+    /// How often the newly closed candlesticks are fetched, it bounds how stale the stored data gets
+    pub interval: Duration,
+}
+
+/// Candlesticks stores of the pipelines, cloned into every stage
+#[derive(Clone)]
+struct Repositories {
+    write_service: Arc<dyn CandlesticksWriteService + Send + Sync>,
+    read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+    /// Jobs are not resumable without it
+    checkpoints: Option<Arc<dyn DownloadCheckpointsService + Send + Sync>>,
+}
+
+/// Wiring of an [`Application`], the exchange clients and the stores can be replaced, e.g. by mocks or another database.
+/// Without replacements the exchange is Binance, the symbols are fetched once and jobs are not resumable.
+pub struct ApplicationBuilder {
+    downstream_buffer: usize,
+    concurrency: usize,
+    refresh_policy: RefreshPolicy,
+    exchange_info_api: Arc<dyn ExchangeInfoApi + Send + Sync>,
+    klines_api: Arc<dyn KlinesApi + Send + Sync>,
+    listing_dates: Option<Arc<dyn ListingDatesService + Send + Sync>>,
+    repositories: Repositories,
+}
+
+impl ApplicationBuilder {
+    pub fn new(
+        downstream_buffer: usize,
+        concurrency: usize,
+        write_service: Arc<dyn CandlesticksWriteService + Send + Sync>,
+        read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+    ) -> Self {
+        let binance = binance_spot_client(Client::new());
+        Self {
+            downstream_buffer,
+            concurrency,
+            refresh_policy: OneShot,
+            exchange_info_api: binance.clone(),
+            klines_api: binance,
+            listing_dates: None,
+            repositories: Repositories {
+                write_service,
+                read_service,
+                checkpoints: None,
+            },
+        }
+    }
+
+    /// Source of the listed symbols
+    pub fn with_exchange_info_api(mut self, exchange_info_api: Arc<dyn ExchangeInfoApi + Send + Sync>) -> Self {
+        self.exchange_info_api = exchange_info_api;
+        self
+    }
+
+    /// Source of the candlesticks
+    pub fn with_klines_api(mut self, klines_api: Arc<dyn KlinesApi + Send + Sync>) -> Self {
+        self.klines_api = klines_api;
+        self
+    }
+
+    /// Job plans and checkpoints store, required by [`Application::resume_pipeline`]
+    pub fn with_checkpoints(mut self, checkpoints: Arc<dyn DownloadCheckpointsService + Send + Sync>) -> Self {
+        self.repositories.checkpoints = Some(checkpoints);
+        self
+    }
+
+    /// Listing dates store, without it every run looks the listing dates up on the exchange again
+    pub fn with_listing_dates(mut self, listing_dates: Arc<dyn ListingDatesService + Send + Sync>) -> Self {
+        self.listing_dates = Some(listing_dates);
+        self
+    }
+
+    /// Symbols are refreshed every `symbols_refresh` to pick up new listings, e.g. for [`Application::start_collector`]
+    pub fn with_symbols_refresh(mut self, symbols_refresh: Duration) -> Self {
+        self.refresh_policy = Periodic(symbols_refresh);
+        self
+    }
+
+    /// Starts the actors, it has to run within an actix system
+    pub fn build(self) -> Application {
+        let symbols_fetcher_actor = SymbolsFetcherActor::new(self.refresh_policy, self.exchange_info_api);
+        // `concurrency` is where the adaptive concurrency starts, it may double while the exchange and the store keep up
+        let mut history_streaming_actor = CandlesticksDownloaderActor::new(self.downstream_buffer, self.concurrency, self.klines_api, BINANCE_RATE_LIMIT)
+            .with_max_concurrency(self.concurrency * 2);
+        if let Some(checkpoints) = self.repositories.checkpoints.clone() {
+            history_streaming_actor = history_streaming_actor.with_checkpoints(checkpoints);
+        }
+        if let Some(listing_dates) = self.listing_dates {
+            history_streaming_actor = history_streaming_actor.with_listing_dates_store(listing_dates);
+        }
+
+        Application {
+            repositories: self.repositories,
+            symbols_fetcher_actor: symbols_fetcher_actor.start(),
+            candlesticks_downloader_actor: history_streaming_actor.start(),
+            cancelled: watch::Sender::new(false),
+        }
+    }
+}
 
 pub struct Application {
-    candlesticks_repository: Arc<CandlesticksRepository>,
+    repositories: Repositories,
     symbols_fetcher_actor: Addr<SymbolsFetcherActor>,
     candlesticks_downloader_actor: Addr<CandlesticksDownloaderActor>,
+    cancelled: watch::Sender<bool>,
 }
 
 impl Application {
     pub fn new(downstream_buffer: usize, concurrency: usize, app_config: AppConfig) -> Self {
-        // infrastructure
-        let http_client = Client::new();
-        let binance = binance_spot_client(http_client.clone());
-        let clickhouse = clickhouse_client(app_config.clickhouse);
-        let candlesticks_repository = Arc::new(CandlesticksRepository::new(clickhouse));
+        Self::clickhouse_builder(downstream_buffer, concurrency, app_config).build()
+    }
 
-        // actors
-        let symbols_fetcher_actor = SymbolsFetcherActor::new(OneShot, binance.clone());
-        let history_streaming_actor = CandlesticksDownloaderActor::new(downstream_buffer, concurrency, binance.clone(), BINANCE_RATE_LIMIT);
+    /// Application for [`Application::start_collector`], symbols are refreshed every `symbols_refresh` to pick up new listings
+    pub fn new_collector(downstream_buffer: usize, concurrency: usize, app_config: AppConfig, symbols_refresh: Duration) -> Self {
+        Self::clickhouse_builder(downstream_buffer, concurrency, app_config)
+            .with_symbols_refresh(symbols_refresh)
+            .build()
+    }
 
-        Application {
-            candlesticks_repository,
-            symbols_fetcher_actor: symbols_fetcher_actor.start(),
-            candlesticks_downloader_actor: history_streaming_actor.start(),
-        }
+    /// Binance and the configured clickhouse, which stores the candlesticks, the job checkpoints and the listing dates.
+    /// With a configured spool the batches clickhouse fails to insert are kept on disk and replayed later.
+    pub fn clickhouse_builder(downstream_buffer: usize, concurrency: usize, app_config: AppConfig) -> ApplicationBuilder {
+        let candlesticks_repository = Arc::new(CandlesticksRepository::from_config(app_config.clickhouse));
+        let write_service: Arc<dyn CandlesticksWriteService + Send + Sync> = match app_config.spool {
+            Some(spool) => Arc::new(SpooledWriteService::new(candlesticks_repository.clone(), spool.path)),
+            None => candlesticks_repository.clone(),
+        };
+
+        ApplicationBuilder::new(downstream_buffer, concurrency, write_service, candlesticks_repository.clone())
+            .with_checkpoints(candlesticks_repository.clone())
+            .with_listing_dates(candlesticks_repository)
+    }
+
+    /// Runs the download and reports it, a cancelled run is reported as well.
+    /// Fails only when nothing could be downloaded at all, failed windows and inserts end up in the report.
+    pub async fn start_pipeline(&self, request: DownloadRequest) -> Result<DownloadReport, HistoryDownloaderError> {
+        let repositories = self.repositories.clone();
+        let symbols_fetcher_actor = self.symbols_fetcher_actor.clone();
+        let candlesticks_downloader_actor = self.candlesticks_downloader_actor.clone();
+        let cancelled = self.cancelled.subscribe();
+        let submitted = SubmittedJobs::default();
+
+        let downloads = stream::once({
+            let submitted = submitted.clone();
+            async move {
+                repositories.write_service.init().await?;
+                let symbols = Self::watch_binance_symbols(symbols_fetcher_actor)
+                    .await?
+                    .next()
+                    .await
+                    .ok_or(HistoryDownloaderError::SymbolsUnavailable)?;
+                if *cancelled.borrow() {
+                    return Ok(stream::empty().boxed());
+                }
+                Self::start_download(request, symbols, repositories.read_service, candlesticks_downloader_actor, submitted).await
+            }
+        });
+
+        self.run_pipeline(downloads, submitted).await
+    }
+
+    /// Estimates what [`Application::start_pipeline`] would fetch and how long it would take, nothing is downloaded or stored.
+    /// Only the symbols and the stored candlesticks are read, the application can still run the download afterwards.
+    pub async fn plan_pipeline(&self, request: DownloadRequest) -> Result<DownloadPlan, HistoryDownloaderError> {
+        let symbols = Self::watch_binance_symbols(self.symbols_fetcher_actor.clone())
+            .await?
+            .next()
+            .await
+            .ok_or(HistoryDownloaderError::SymbolsUnavailable)?;
+        let windows = Self::plan_windows(request, symbols, self.repositories.read_service.clone()).await;
+        Ok(self.candlesticks_downloader_actor.send(PlanDownload { windows }).await?)
     }
 
-    pub async fn start_pipeline(&self, timeframe: Timeframe, start_date: OffsetDateTime, filter: SymbolFilterFn) {
-        let candlesticks_repository = self.candlesticks_repository.clone();
+    /// Continues a job of a previous run, e.g. one killed in the middle, right after its last committed checkpoints
+    pub async fn resume_pipeline(&self, job_id: JobId) -> Result<DownloadReport, HistoryDownloaderError> {
+        let repositories = self.repositories.clone();
+        let candlesticks_downloader_actor = self.candlesticks_downloader_actor.clone();
+        let submitted = SubmittedJobs::default();
+
+        let downloads = stream::once({
+            let submitted = submitted.clone();
+            async move {
+                repositories.write_service.init().await?;
+                Self::download_candlesticks_into_stream(ResumeJob { job_id }, candlesticks_downloader_actor, submitted).await
+            }
+        });
+
+        self.run_pipeline(downloads, submitted).await
+    }
+
+    /// Keeps the scheduled timeframes current until [`Application::cancel`]. Every interval each listed symbol continues
+    /// from its last stored candlestick, a round starts only once the previous round of the timeframe is downloaded.
+    pub async fn start_collector(&self, schedule: CollectorSchedule) -> Result<DownloadReport, HistoryDownloaderError> {
+        let repositories = self.repositories.clone();
         let symbols_fetcher_actor = self.symbols_fetcher_actor.clone();
         let candlesticks_downloader_actor = self.candlesticks_downloader_actor.clone();
+        let cancelled = self.cancelled.subscribe();
 
-        let _ = tokio_stream::once(true)
-            .then(|_| Self::init_candlestick_repository(candlesticks_repository.clone()))
-            .then(|_| Self::watch_binance_symbols(symbols_fetcher_actor.clone()))
-            .flatten()
-            .take(1)
-            .map(|symbols| Self::download_candlesticks_command(timeframe.clone(), start_date, symbols, filter.clone()))
-            .then(|command| Self::download_candlesticks_into_stream(command, candlesticks_downloader_actor.clone()))
+        let submitted = SubmittedJobs::default();
+
+        let optimization_interval = schedule.optimization_interval;
+        let collector = stream::once({
+            let cancelled = cancelled.clone();
+            let submitted = submitted.clone();
+            async move {
+                repositories.write_service.init().await?;
+                let symbols = Self::latest_binance_symbols(symbols_fetcher_actor).await?;
+                let rounds = Self::collect_periodically(
+                    schedule,
+                    symbols,
+                    repositories.read_service,
+                    candlesticks_downloader_actor,
+                    cancelled,
+                    submitted,
+                );
+                Ok(rounds.boxed())
+            }
+        })
+        .take_until(Self::cancellation(cancelled.clone()));
+
+        let optimization = self.optimize_periodically(optimization_interval, cancelled.clone());
+        let (summary, _) = futures::join!(self.run_pipeline(collector, submitted), optimization);
+        summary
+    }
+
+    async fn run_pipeline(
+        &self,
+        downloads: impl Stream<Item = Result<BoxStream<'static, JobCandlesticks>, HistoryDownloaderError>>,
+        submitted: SubmittedJobs,
+    ) -> Result<DownloadReport, HistoryDownloaderError> {
+        let repositories = self.repositories.clone();
+        let timer = Instant::now();
+
+        let mut fatal = None;
+        let mut rows_inserted = HashMap::<Arc<Symbol>, usize>::new();
+        let mut rows_not_inserted = 0;
+        downloads
+            .filter_map(|download| ready(download.map_err(|err| fatal = Some(err)).ok()))
             .flat_map_unordered(8, |candlesticks| candlesticks)
             .chunks(8)
-            .then(|chunk| Self::persist_candlesticks(chunk, candlesticks_repository.clone()))
-            .inspect_err(|err| error!("Error during main pipeline: {}", err))
-            .for_each(|_| async {})
+            .then(|chunk| {
+                let rows = Self::rows_per_symbol(&chunk);
+                Self::persist_candlesticks(chunk, repositories.clone()).map(|result| (rows, result))
+            })
+            .for_each(|(rows, result)| {
+                match result {
+                    Ok(_) => rows.into_iter().for_each(|(symbol, count)| *rows_inserted.entry(symbol).or_default() += count),
+                    Err(err) => {
+                        error!("Error during main pipeline: {}", err);
+                        rows_not_inserted += rows.values().sum::<usize>();
+                    }
+                }
+                ready(())
+            })
             .await;
 
-        let _ = symbols_fetcher_actor.send(symbols_fetcher_actor::commands::Command::Shutdown).await;
+        // the job channels are closed, every submitted job is finished
+        let jobs = submitted.lock().unwrap().iter().map(|progress| progress.borrow().clone()).collect();
+
+        match fatal {
+            Some(err) => {
+                error!("Download pipeline failed: {}", err);
+                Err(err)
+            }
+            None => Ok(DownloadReport::new(jobs, rows_inserted, rows_not_inserted, timer.elapsed())),
+        }
+    }
+
+    /// Stops a running [`Application::start_pipeline`] or [`Application::start_collector`]:
+    /// pages in flight and buffered candlesticks are still persisted
+    pub async fn cancel(&self) {
+        self.cancelled.send_replace(true);
+        let _ = self
+            .candlesticks_downloader_actor
+            .send(CancelDownload { job_id: None })
+            .await
+            .inspect_err(|err| error!("Failed to cancel the download: {}", err));
+    }
 
-        let _ = candlesticks_downloader_actor
+    /// Stops the symbols fetcher and the downloader, queued jobs are dropped and running ones cancelled.
+    /// No pipeline can run afterwards, dropping the application shuts the actors down as well.
+    pub async fn shutdown(&self) {
+        let _ = self.symbols_fetcher_actor.send(symbols_fetcher_actor::commands::Command::Shutdown).await;
+        let _ = self
+            .candlesticks_downloader_actor
             .send(candlesticks_downloader_actor::commands::shutdown::Command::Shutdown)
             .await;
     }
 
     pub async fn run_optimization(&self) {
         let _ = self
-            .candlesticks_repository
-            .clone()
+            .repositories
+            .write_service
             .run_optimization()
             .inspect_err(|err| error!("Error during clickhouse optimization: {}", err))
             .await;
     }
 
-    async fn init_candlestick_repository(candlesticks_repository: Arc<CandlesticksRepository>) -> Result<(), ClickhouseRepositoryError> {
-        candlesticks_repository.init().await
+    /// Crashes and restarts of the symbols fetcher and the downloader from now on, e.g. to alert on a `GaveUp`.
+    /// Events a slow subscriber missed are skipped.
+    pub async fn lifecycle_events(&self) -> Result<BoxStream<'static, LifecycleEvent>, HistoryDownloaderError> {
+        let symbols_fetcher_events = self.symbols_fetcher_actor.send(symbols_fetcher_actor::GetLifecycleReceiver).await?;
+        let downloader_events = self.candlesticks_downloader_actor.send(GetLifecycleReceiver).await?;
+
+        Ok(
+            stream::select(BroadcastStream::new(symbols_fetcher_events), BroadcastStream::new(downloader_events))
+                .filter_map(|event| ready(event.ok()))
+                .boxed(),
+        )
+    }
+
+    async fn watch_binance_symbols(symbols_fetcher_actor: Addr<SymbolsFetcherActor>) -> Result<BoxStream<'static, Arc<Symbols>>, HistoryDownloaderError> {
+        let receiver = symbols_fetcher_actor.send(GetReceiver).await?;
+        let mut status_receiver = symbols_fetcher_actor.send(GetStatusReceiver).await?;
+
+        let symbols_failed = async move {
+            if let Ok(status) = status_receiver.wait_for(|status| matches!(status, SymbolsStatus::Failed { .. })).await {
+                error!("Binance symbols are unavailable: {:?}", *status);
+            }
+        };
+
+        Ok(WatchStream::new(receiver)
+            .filter_map(|item| async move { item })
+            .take_until(symbols_failed)
+            .boxed())
+    }
+
+    /// Waits for the first symbols snapshot, later refreshes are read from the receiver when a round starts
+    async fn latest_binance_symbols(symbols_fetcher_actor: Addr<SymbolsFetcherActor>) -> Result<watch::Receiver<Option<Arc<Symbols>>>, HistoryDownloaderError> {
+        let mut receiver = symbols_fetcher_actor.send(GetReceiver).await?;
+        let _ = receiver.wait_for(Option::is_some).await;
+        Ok(receiver)
+    }
+
+    async fn cancellation(mut cancelled: watch::Receiver<bool>) {
+        let _ = cancelled.wait_for(|cancelled| *cancelled).await;
+    }
+
+    fn collect_periodically(
+        schedule: CollectorSchedule,
+        symbols: watch::Receiver<Option<Arc<Symbols>>>,
+        read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+        candlesticks_downloader_actor: Addr<CandlesticksDownloaderActor>,
+        cancelled: watch::Receiver<bool>,
+        submitted: SubmittedJobs,
+    ) -> impl Stream<Item = JobCandlesticks> + Send + 'static {
+        let filter = schedule.filter;
+        stream::select_all(schedule.timeframes.into_iter().map(move |timeframe_schedule| {
+            let mut interval = tokio::time::interval(timeframe_schedule.interval);
+            // a round longer than the interval delays the next one instead of queueing a burst of them
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            let filter = filter.clone();
+            let symbols = symbols.clone();
+            let read_service = read_service.clone();
+            let candlesticks_downloader_actor = candlesticks_downloader_actor.clone();
+            let submitted = submitted.clone();
+            // only new rounds are stopped, the cancelled round still flushes its pages
+            IntervalStream::new(interval)
+                .take_until(Self::cancellation(cancelled.clone()))
+                .then(move |_| {
+                    let symbols = symbols.borrow().clone();
+                    Self::collect_timeframe(
+                        timeframe_schedule.clone(),
+                        symbols,
+                        filter.clone(),
+                        read_service.clone(),
+                        candlesticks_downloader_actor.clone(),
+                        submitted.clone(),
+                    )
+                })
+                .flatten()
+                .boxed()
+        }))
     }
 
-    async fn watch_binance_symbols(symbols_fetcher_actor: Addr<SymbolsFetcherActor>) -> BoxStream<'static, Arc<Symbols>> {
-        async {
-            let receiver = symbols_fetcher_actor.send(GetReceiver).await.expect("Failed to get symbols receiver");
+    async fn collect_timeframe(
+        timeframe_schedule: TimeframeSchedule,
+        symbols: Option<Arc<Symbols>>,
+        filter: SymbolFilterFn,
+        read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+        candlesticks_downloader_actor: Addr<CandlesticksDownloaderActor>,
+        submitted: SubmittedJobs,
+    ) -> BoxStream<'static, JobCandlesticks> {
+        let Some(symbols) = symbols else {
+            return stream::empty().boxed();
+        };
+        let TimeframeSchedule { timeframe, start_date, .. } = timeframe_schedule;
+        let resume_from = Self::fetch_resume_points(DownloadMode::Resume, vec![timeframe.clone()], read_service).await;
+        info!("Collecting {} candlesticks, {} symbols are listed", timeframe, symbols.len());
+        let description = timeframe.to_string();
 
-            WatchStream::new(receiver).filter_map(|item| async move { item })
+        let command = DownloadCandlesticks {
+            symbols,
+            timeframes: vec![(timeframe, start_date)],
+            end_date: None,
+            filter,
+            resume_from: Arc::new(resume_from),
+        };
+        // the next round retries, a collector is never stopped by a single failed round
+        Self::download_candlesticks_into_stream(command, candlesticks_downloader_actor, submitted)
+            .await
+            .unwrap_or_else(|err| {
+                error!("Failed to collect {} candlesticks: {}", description, err);
+                stream::empty().boxed()
+            })
+    }
+
+    /// The first optimization runs one interval after the start
+    async fn optimize_periodically(&self, interval: Option<Duration>, cancelled: watch::Receiver<bool>) {
+        let Some(interval) = interval else { return };
+        IntervalStream::new(tokio::time::interval_at(Instant::now() + interval, interval))
+            .take_until(Self::cancellation(cancelled))
+            .for_each(|_| async {
+                info!("Running scheduled clickhouse optimization");
+                self.run_optimization().await;
+            })
+            .await
+    }
+
+    async fn start_download(
+        request: DownloadRequest,
+        symbols: Arc<Symbols>,
+        read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+        candlesticks_downloader_actor: Addr<CandlesticksDownloaderActor>,
+        submitted: SubmittedJobs,
+    ) -> Result<BoxStream<'static, JobCandlesticks>, HistoryDownloaderError> {
+        match request.mode {
+            DownloadMode::Full | DownloadMode::Resume => {
+                let timeframes = request.timeframes.iter().map(|(timeframe, _)| timeframe.clone()).collect();
+                let resume_from = Self::fetch_resume_points(request.mode, timeframes, read_service).await;
+                let command = Self::download_candlesticks_command(request, symbols, resume_from);
+                Self::download_candlesticks_into_stream(command, candlesticks_downloader_actor, submitted).await
+            }
+            DownloadMode::Backfill => {
+                let windows = Self::fetch_backfill_windows(request, symbols, read_service).await;
+                info!("Backfilling {} gaps", windows.len());
+                Self::download_candlesticks_into_stream(BackfillCandlesticks { windows }, candlesticks_downloader_actor, submitted).await
+            }
         }
-        .await
-        .boxed()
     }
 
-    async fn download_candlesticks_into_stream(
-        download: DownloadCandlesticks,
+    /// Same windows as [`Application::start_download`] submits
+    async fn plan_windows(
+        request: DownloadRequest,
+        symbols: Arc<Symbols>,
+        read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+    ) -> Vec<CandlesticksWindow> {
+        match request.mode {
+            DownloadMode::Full | DownloadMode::Resume => {
+                let timeframes = request.timeframes.iter().map(|(timeframe, _)| timeframe.clone()).collect();
+                let resume_from = Self::fetch_resume_points(request.mode, timeframes, read_service).await;
+                Self::download_candlesticks_command(request, symbols, resume_from).windows()
+            }
+            DownloadMode::Backfill => Self::fetch_backfill_windows(request, symbols, read_service).await,
+        }
+    }
+
+    async fn fetch_resume_points(
+        mode: DownloadMode,
+        timeframes: Vec<Timeframe>,
+        read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+    ) -> LastOpenTimes {
+        match mode {
+            DownloadMode::Full | DownloadMode::Backfill => LastOpenTimes::new(),
+            DownloadMode::Resume => read_service
+                .fetch_last_open_times(timeframes)
+                .await
+                .inspect_err(|err| error!("Failed to fetch resume points, falling back to start_date: {}", err))
+                .unwrap_or_default(),
+        }
+    }
+
+    async fn fetch_backfill_windows(
+        request: DownloadRequest,
+        symbols: Arc<Symbols>,
+        read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+    ) -> Vec<CandlesticksWindow> {
+        let filter = request.filter;
+        let listed_symbols = symbols.iter().filter(|symbol| filter(symbol)).cloned().collect::<Vec<_>>();
+        let end_date = request.end_date.unwrap_or_else(OffsetDateTime::now_utc);
+
+        stream::iter(request.timeframes)
+            .then(|(timeframe, start_date)| read_service.fetch_gaps(listed_symbols.clone(), timeframe, start_date, end_date))
+            .flat_map(|gaps| {
+                let gaps = gaps.inspect_err(|err| error!("Failed to fetch candlesticks gaps: {}", err)).unwrap_or_default();
+                stream::iter(gaps)
+            })
+            .collect()
+            .await
+    }
+
+    async fn download_candlesticks_into_stream<M>(
+        download: M,
         candlesticks_downloader_actor: Addr<CandlesticksDownloaderActor>,
-    ) -> BoxStream<'static, Vec<Candlestick>> {
-        async {
-            let candlestick_receiver = candlesticks_downloader_actor
-                .send(download)
+        submitted: SubmittedJobs,
+    ) -> Result<BoxStream<'static, JobCandlesticks>, HistoryDownloaderError>
+    where
+        M: Message<Result = Result<JobReceiver<WindowPage>, DownloadHistoryError>> + Send + 'static,
+        CandlesticksDownloaderActor: Handler<M>,
+    {
+        let JobReceiver { job_id, receiver } = candlesticks_downloader_actor
+            .send(download)
+            .await?
+            .map_err(HistoryDownloaderError::JobRejected)?;
+        info!("Download job {} is submitted", job_id);
+        if let Some(progress) = candlesticks_downloader_actor.send::<GetProgressReceiver>(GetProgressReceiver(job_id)).await? {
+            submitted.lock().unwrap().push(progress);
+        }
+
+        Ok(ReceiverStream::new(receiver).map(move |page| (job_id, page)).boxed())
+    }
+
+    /// Checkpoints are committed only once the candlesticks are stored, a resumed job never skips a lost page
+    async fn persist_candlesticks(chunk: Vec<JobCandlesticks>, repositories: Repositories) -> Result<(), ClickhouseRepositoryError> {
+        let checkpoints = Self::latest_open_times(&chunk);
+        let candlesticks = chunk.into_iter().map(|(_, (_, candlesticks))| candlesticks).collect();
+        repositories.write_service.bulk_insert_candlesticks(candlesticks).await?;
+
+        // the rows are stored either way, a resumed job just fetches them again
+        let Some(checkpoints_service) = repositories.checkpoints else { return Ok(()) };
+        for (job_id, checkpoints) in checkpoints {
+            let _ = checkpoints_service
+                .commit_checkpoints(job_id, checkpoints)
                 .await
-                .expect("Failed send message into [CandlesticksDownloaderActor]")
-                .expect("Failed to get candlestick receiver from [CandlesticksDownloaderActor]");
+                .inspect_err(|err| error!("Failed to commit checkpoints of job {}: {}", job_id, err));
+        }
+        Ok(())
+    }
 
-            ReceiverStream::new(candlestick_receiver)
+    fn rows_per_symbol(chunk: &[JobCandlesticks]) -> HashMap<Arc<Symbol>, usize> {
+        let mut rows = HashMap::<Arc<Symbol>, usize>::new();
+        for candlestick in chunk.iter().flat_map(|(_, (_, candlesticks))| candlesticks) {
+            *rows.entry(candlestick.symbol.clone()).or_default() += 1;
         }
-        .await
-        .boxed()
+        rows
     }
 
-    async fn persist_candlesticks(chunk: Vec<Vec<Candlestick>>, candlesticks_repository: Arc<CandlesticksRepository>) -> Result<(), ClickhouseRepositoryError> {
-        candlesticks_repository.bulk_insert_candlesticks(chunk).await
+    /// Checkpoints per window, a job may download several windows of a pair.
+    /// A still-open candlestick is not a checkpoint, the resumed job has to fetch it again once it is closed
+    fn latest_open_times(chunk: &[JobCandlesticks]) -> HashMap<JobId, WindowCheckpoints> {
+        let mut checkpoints = HashMap::<JobId, WindowCheckpoints>::new();
+        for (job_id, (window_start, candlesticks)) in chunk {
+            let last_open_times = checkpoints.entry(*job_id).or_default();
+            for candlestick in candlesticks.iter().filter(|candlestick| candlestick.is_closed) {
+                last_open_times
+                    .entry((candlestick.symbol.clone(), candlestick.timeframe.clone(), *window_start))
+                    .and_modify(|open_time| *open_time = candlestick.open_time.max(*open_time))
+                    .or_insert(candlestick.open_time);
+            }
+        }
+        checkpoints
     }
 
-    fn download_candlesticks_command(timeframe: Timeframe, start_date: OffsetDateTime, symbols: Arc<Symbols>, filter: SymbolFilterFn) -> DownloadCandlesticks {
+    fn download_candlesticks_command(request: DownloadRequest, symbols: Arc<Symbols>, resume_from: LastOpenTimes) -> DownloadCandlesticks {
         DownloadCandlesticks {
             symbols,
-            timeframe: timeframe.clone(),
-            start_date,
-            filter: filter.clone(),
+            timeframes: request.timeframes,
+            end_date: request.end_date,
+            filter: request.filter,
+            resume_from: Arc::new(resume_from),
         }
     }
 }
 
+impl Drop for Application {
+    fn drop(&mut self) {
+        self.symbols_fetcher_actor.do_send(symbols_fetcher_actor::commands::Command::Shutdown);
+        self.candlesticks_downloader_actor
+            .do_send(candlesticks_downloader_actor::commands::shutdown::Command::Shutdown);
+    }
+}
+
+/// Failure that stops a pipeline before anything could be downloaded
 #[derive(Debug, Error)]
-pub enum HistoryGrabberError<T> {
-    #[error("HttpResponseError {source}")]
-    UnexpectedHttpResponse {
-        #[from]
-        #[source]
-        source: HttpResponseError,
-    },
-
-    #[error("Failed to send message: {0}")]
-    UnexpectedSendResult(#[from] SendError<T>),
-
-    #[error("Unexpected error {0}")]
-    Unexpected(String),
+pub enum HistoryDownloaderError {
+    #[error("Failed to prepare the candlesticks storage: {0}")]
+    Storage(#[from] ClickhouseRepositoryError),
+
+    #[error("Binance symbols are unavailable")]
+    SymbolsUnavailable,
+
+    #[error("Download job was rejected: {0:?}")]
+    JobRejected(DownloadHistoryError),
+
+    #[error("Actor is unreachable: {0}")]
+    ActorUnavailable(#[from] MailboxError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::timeframe::Timeframe::OneDay;
+    use candy_ass_core::mocks::fixtures::BTC_USDT_CANDLESTICK;
+    use time::format_description::well_known::Rfc3339;
+
+    #[test]
+    fn test_checkpoints_are_kept_per_window() {
+        // given: two gaps of the same pair, only the later one is finished
+        let first_gap = OffsetDateTime::parse("2024-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let second_gap = first_gap + time::Duration::days(5);
+        let candlestick = |open_time| {
+            let mut candlestick = BTC_USDT_CANDLESTICK.clone();
+            candlestick.open_time = open_time;
+            candlestick
+        };
+        let chunk = vec![
+            (7, (first_gap, vec![candlestick(first_gap)])),
+            (
+                7,
+                (second_gap, vec![candlestick(second_gap), candlestick(second_gap + time::Duration::days(1))]),
+            ),
+        ];
+
+        // when
+        let checkpoints = Application::latest_open_times(&chunk);
+
+        // then
+        let symbol = BTC_USDT_CANDLESTICK.symbol.clone();
+        let job_checkpoints = &checkpoints[&7];
+        assert_eq!(2, job_checkpoints.len());
+        assert_eq!(Some(&first_gap), job_checkpoints.get(&(symbol.clone(), OneDay, first_gap)));
+        assert_eq!(
+            Some(&(second_gap + time::Duration::days(1))),
+            job_checkpoints.get(&(symbol, OneDay, second_gap))
+        );
+    }
 }
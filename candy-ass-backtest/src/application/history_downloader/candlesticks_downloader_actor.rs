@@ -1,25 +1,123 @@
 pub mod commands;
+pub mod concurrency;
 pub mod errors;
+pub mod listing_dates;
+pub mod plan;
+pub mod progress;
 pub mod queries;
+pub mod shards;
 
-use crate::application::history_downloader::candlesticks_downloader_actor::Status::Ready;
-use actix::{Actor, Context};
+use crate::application::history_downloader::candlesticks_downloader_actor::concurrency::{ConcurrencyController, RequestPacer};
+use crate::application::history_downloader::candlesticks_downloader_actor::listing_dates::ListingDates;
+use crate::application::history_downloader::candlesticks_downloader_actor::progress::{DownloadProgress, WindowState};
+use crate::application::job_queue::JobQueue;
+use crate::integrations::clickhouse::candlesticks_repository::{DownloadCheckpointsService, JobId, ListingDatesService};
+use actix::{Actor, ActorFutureExt, AsyncContext, Context, WrapFuture};
+use candy_ass_core::application::supervision::{LifecycleEvent, RestartPolicy, Supervision};
+use candy_ass_core::domain::candlestick::Candlestick;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
 use candy_ass_core::integrations::http::binance::spot_http_client::KlinesApi;
+use candy_ass_core::utils::RetryPolicy;
 use std::sync::Arc;
-use tracing::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::{mpsc, watch};
+use tracing::{error, info};
 
-#[derive(Debug, Clone)]
-enum Status {
-    Ready,
-    Busy,
+/// Requests per date shard unless configured otherwise
+pub const DEFAULT_SHARD_PAGES: usize = 10;
+
+/// What to do with the still-open candlestick the latest page usually ends with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PartialCandlePolicy {
+    /// Never stored, the next top-up run fetches it once it is closed
+    #[default]
+    Skip,
+    /// Stored with `is_closed = false`, the closed version replaces it when it is downloaded again
+    Overwrite,
+}
+
+impl PartialCandlePolicy {
+    pub fn apply(self, candlesticks: Vec<Candlestick>) -> Vec<Candlestick> {
+        match self {
+            PartialCandlePolicy::Skip => candlesticks.into_iter().filter(|candlestick| candlestick.is_closed).collect(),
+            PartialCandlePolicy::Overwrite => candlesticks,
+        }
+    }
+}
+
+/// Page of a job window along with the window start recorded in the job plan, checkpoints of the window are keyed by it
+pub type WindowPage = (OffsetDateTime, Vec<Candlestick>);
+
+/// Queued or running download. `windows` are handed over to the job once it starts, with their progress index,
+/// `sender` is kept until the job finishes so a crashed job can be restarted into the same channel.
+struct DownloadJob {
+    windows: Vec<(usize, CandlesticksWindow)>,
+    /// Planned start of every window by progress index, it stays the same when a window is trimmed or restarted
+    planned_starts: Vec<OffsetDateTime>,
+    /// Whether the windows are recorded as a new job plan, resumed jobs are already registered
+    register_job: bool,
+    sender: Option<mpsc::Sender<WindowPage>>,
+    progress_sender: watch::Sender<DownloadProgress>,
+    /// Raised to stop the job at the next page boundary
+    cancelled: Arc<AtomicBool>,
+}
+
+impl DownloadJob {
+    /// Returns whether the job was still running or queued.
+    /// A queued job never starts, its windows are cancelled and its receiver is closed right away.
+    fn cancel(&mut self, queued: bool) -> bool {
+        let was_cancelled = self.cancelled.swap(true, Ordering::SeqCst);
+        self.sender.take();
+        if queued {
+            self.progress_sender.send_modify(|progress| progress.close_unfinished(WindowState::Cancelled));
+        }
+        !was_cancelled
+    }
+
+    /// Windows a restarted job still has to download: pending ones as they are,
+    /// running ones from the candlestick after the latest one that was sent downstream
+    fn remaining_windows(&self) -> Vec<(usize, CandlesticksWindow)> {
+        let progress = self.progress_sender.borrow();
+        progress
+            .windows
+            .iter()
+            .enumerate()
+            .filter_map(|(index, window)| match (&window.state, window.last_fetched) {
+                (WindowState::Pending, _) | (WindowState::Running, None) => Some((index, window.window.clone())),
+                (WindowState::Running, Some(last_fetched)) => {
+                    let mut window = window.window.clone();
+                    window.start = last_fetched + time::Duration::MILLISECOND;
+                    Some((index, window))
+                }
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 pub struct CandlesticksDownloaderActor {
     downstream_buffer: usize,
     concurrency: usize,
+    /// Adapts the page requests in flight between 1 and its max, shared by every job
+    concurrency_controller: ConcurrencyController,
+    /// Spaces every request of the actor `binance_rate_limit` milliseconds apart, shared by every job
+    request_pacer: RequestPacer,
     binance_client: Arc<dyn KlinesApi + Send + Sync>,
     binance_rate_limit: usize,
-    status: Status,
+    retry_policy: RetryPolicy,
+    partial_candle_policy: PartialCandlePolicy,
+    /// Requests per date shard, the shards of a window are fetched in parallel
+    shard_pages: usize,
+    /// Job plans and checkpoints store, jobs are not resumable without it
+    checkpoints: Option<Arc<dyn DownloadCheckpointsService + Send + Sync>>,
+    /// Shared by every job of the actor, so a symbol is looked up only once
+    listing_dates: ListingDates,
+    jobs: JobQueue<DownloadJob>,
+    /// Restarts the jobs whose download panicked from their progress, every job has its own restart budget
+    supervision: Supervision<JobId>,
+    shutdown_requested: bool,
 }
 
 impl CandlesticksDownloaderActor {
@@ -33,17 +131,86 @@ impl CandlesticksDownloaderActor {
         CandlesticksDownloaderActor {
             downstream_buffer,
             concurrency,
+            concurrency_controller: ConcurrencyController::new(concurrency, concurrency),
+            request_pacer: RequestPacer::new(Duration::from_millis(binance_rate_limit as u64)),
             binance_client,
             binance_rate_limit,
-            status: Ready,
+            retry_policy: RetryPolicy::default(),
+            partial_candle_policy: PartialCandlePolicy::default(),
+            shard_pages: DEFAULT_SHARD_PAGES,
+            checkpoints: None,
+            listing_dates: ListingDates::default(),
+            jobs: JobQueue::new(1),
+            supervision: Supervision::new("CandlesticksDownloaderActor"),
+            shutdown_requested: false,
         }
     }
+
+    /// Lets the concurrency grow above its initial value while the exchange and the downstream keep up
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.concurrency_controller = ConcurrencyController::new(self.concurrency, max_concurrency.max(self.concurrency));
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_partial_candle_policy(mut self, partial_candle_policy: PartialCandlePolicy) -> Self {
+        self.partial_candle_policy = partial_candle_policy;
+        self
+    }
+
+    /// Smaller shards let fewer symbols use the whole `concurrency`, at the cost of more buffered pages
+    pub fn with_shard_pages(mut self, shard_pages: usize) -> Self {
+        self.shard_pages = shard_pages.max(1);
+        self
+    }
+
+    pub fn with_checkpoints(mut self, checkpoints: Arc<dyn DownloadCheckpointsService + Send + Sync>) -> Self {
+        self.checkpoints = Some(checkpoints);
+        self
+    }
+
+    /// Discovered listing dates are saved in the store and the persisted ones are loaded on start
+    pub fn with_listing_dates_store(mut self, store: Arc<dyn ListingDatesService + Send + Sync>) -> Self {
+        self.listing_dates = self.listing_dates.with_store(store);
+        self
+    }
+
+    /// Jobs above the limit wait in the queue, the request pacing is shared by the jobs that run together
+    pub fn with_max_concurrent_jobs(mut self, max_concurrent_jobs: usize) -> Self {
+        self.jobs.set_max_running(max_concurrent_jobs);
+        self
+    }
+
+    /// A job that keeps crashing after `max_restarts` restarts fails its unfinished windows
+    pub fn with_restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.supervision.set_policy(restart_policy);
+        self
+    }
 }
 
 impl Actor for CandlesticksDownloaderActor {
     type Context = Context<Self>;
 
+    /// Messages wait until the persisted listing dates are loaded, so known symbols are not looked up again
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !self.listing_dates.is_persisted() {
+            return;
+        }
+        let listing_dates = self.listing_dates.clone();
+        ctx.wait(async move { listing_dates.load().await }.into_actor(self).map(|result, _, _| match result {
+            Ok(count) => info!("[CandlesticksDownloaderActor] loaded {} listing dates", count),
+            Err(err) => error!("[CandlesticksDownloaderActor] failed to load the listing dates: {}", err),
+        }));
+    }
+
     fn stopped(&mut self, _: &mut Self::Context) {
         info!("[CandlesticksDownloaderActor] is stopped");
+        self.supervision.publish(LifecycleEvent::Stopped {
+            actor: "CandlesticksDownloaderActor",
+        });
     }
 }
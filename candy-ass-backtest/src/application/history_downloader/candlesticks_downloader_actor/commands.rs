@@ -1,2 +1,5 @@
+pub mod backfill_candlesticks;
+pub mod cancel_download;
 pub mod download_candlesticks;
+pub mod resume_job;
 pub mod shutdown;
@@ -0,0 +1,20 @@
+use crate::application::history_downloader::candlesticks_downloader_actor::errors::DownloadHistoryError;
+use crate::application::history_downloader::candlesticks_downloader_actor::{CandlesticksDownloaderActor, WindowPage};
+use crate::application::job_queue::JobReceiver;
+use actix::{Handler, Message, MessageResult};
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+
+/// Downloads only the given windows, e.g. gaps reported by `CandlesticksReadService::fetch_gaps`
+#[derive(Message, Clone)]
+#[rtype(result = "Result<JobReceiver<WindowPage>, DownloadHistoryError>")]
+pub struct BackfillCandlesticks {
+    pub windows: Vec<CandlesticksWindow>,
+}
+
+impl Handler<BackfillCandlesticks> for CandlesticksDownloaderActor {
+    type Result = MessageResult<BackfillCandlesticks>;
+
+    fn handle(&mut self, msg: BackfillCandlesticks, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.start_new_job(msg.windows, ctx))
+    }
+}
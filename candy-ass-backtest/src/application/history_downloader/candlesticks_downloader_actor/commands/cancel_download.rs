@@ -0,0 +1,51 @@
+use crate::application::history_downloader::candlesticks_downloader_actor::CandlesticksDownloaderActor;
+use crate::application::job_queue::JobStatus;
+use crate::integrations::clickhouse::candlesticks_repository::JobId;
+use actix::{Handler, Message};
+use tracing::info;
+
+/// Stops a job: in-flight pages are completed and sent downstream, windows are not continued.
+/// A queued job never starts. Without `job_id` every queued and running job is cancelled.
+/// Replies whether there was a job to cancel, the outcome is available through `GetProgress`.
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct CancelDownload {
+    pub job_id: Option<JobId>,
+}
+
+impl Handler<CancelDownload> for CandlesticksDownloaderActor {
+    type Result = bool;
+
+    fn handle(&mut self, msg: CancelDownload, ctx: &mut Self::Context) -> Self::Result {
+        let cancelled = self.cancel_jobs(msg.job_id);
+        // cancelled queued jobs are done already, there may be nothing left to wait for
+        self.schedule(ctx);
+        cancelled
+    }
+}
+
+impl CandlesticksDownloaderActor {
+    pub(super) fn cancel_jobs(&mut self, job_id: Option<JobId>) -> bool {
+        let job_ids: Vec<JobId> = self
+            .jobs
+            .active()
+            .into_iter()
+            .filter(|active| job_id.is_none_or(|job_id| job_id == *active))
+            .collect();
+
+        let mut cancelled = false;
+        for job_id in job_ids {
+            let queued = self.jobs.status(job_id) == Some(JobStatus::Queued);
+            let Some(job) = self.jobs.get_mut(job_id) else { continue };
+            if job.cancel(queued) {
+                info!("[CandlesticksDownloaderActor] is cancelling job {}", job_id);
+                cancelled = true;
+            }
+            if queued {
+                self.jobs.finish(job_id);
+                self.supervision.finished(&job_id);
+            }
+        }
+        cancelled
+    }
+}
@@ -1,128 +1,475 @@
-use crate::application::history_downloader::candlesticks_downloader_actor::errors::DownloadHistoryError;
-use crate::application::history_downloader::candlesticks_downloader_actor::{CandlesticksDownloaderActor, Status};
-use Status::Ready;
-use actix::{ActorFutureExt, AsyncContext, Handler, Message, MessageResult, WrapFuture};
+use crate::application::history_downloader::candlesticks_downloader_actor::concurrency::{ConcurrencyController, RequestPacer};
+use crate::application::history_downloader::candlesticks_downloader_actor::errors::{DownloadHistoryError, DownloadWindowError};
+use crate::application::history_downloader::candlesticks_downloader_actor::listing_dates::{ListingDates, trim_to_listing};
+use crate::application::history_downloader::candlesticks_downloader_actor::progress::{DownloadProgress, WindowState};
+use crate::application::history_downloader::candlesticks_downloader_actor::shards::{PAGE_SIZE, split_into_shards};
+use crate::application::history_downloader::candlesticks_downloader_actor::{CandlesticksDownloaderActor, DownloadJob, WindowPage};
+use crate::application::job_queue::JobReceiver;
+use crate::integrations::clickhouse::candlesticks_repository::{DownloadCheckpointsService, JobId, LastOpenTimes, PlannedWindow};
+use actix::{ActorContext, ActorFutureExt, AsyncContext, Context, Handler, Message, MessageResult, WrapFuture};
+use candy_ass_core::application::supervision::{catch_panic, panic_message};
 use candy_ass_core::domain::candlestick::Candlestick;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
 use candy_ass_core::domain::symbol::{Symbol, SymbolFilterFn, Symbols};
 use candy_ass_core::domain::timeframe::Timeframe;
+use candy_ass_core::integrations::http::HttpResponseError;
+use candy_ass_core::integrations::http::binance::BINANCE_HEADER_USED_WEIGHT_1M;
 use candy_ass_core::integrations::http::binance::spot_http_client::KlinesApi;
+use candy_ass_core::utils::RetryPolicy;
 use futures::Stream;
-use futures_util::{StreamExt, stream};
+use futures_util::{StreamExt, TryFutureExt, TryStreamExt, future, stream};
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
 use time::OffsetDateTime;
-use tokio::sync::mpsc;
-use tokio::time::{Instant, sleep};
+use tokio::sync::{mpsc, watch};
+use tokio::time::Instant;
+use tokio_retry::Retry;
 use tracing::{error, info, warn};
 
 #[derive(Message, Clone)]
-#[rtype(result = "Result<mpsc::Receiver<Vec<Candlestick>>, DownloadHistoryError>")]
+#[rtype(result = "Result<JobReceiver<WindowPage>, DownloadHistoryError>")]
 pub struct DownloadCandlesticks {
     pub symbols: Arc<Symbols>,
-    pub timeframe: Timeframe,
-    pub start_date: OffsetDateTime,
+    /// Timeframes to download, each with its own start date
+    pub timeframes: Vec<(Timeframe, OffsetDateTime)>,
+    /// Inclusive bound for `open_time`, the download runs up to the latest candlestick when it is `None`
+    pub end_date: Option<OffsetDateTime>,
     pub filter: SymbolFilterFn,
+    /// Per (symbol, timeframe) start overrides, used when they are later than the timeframe start date
+    pub resume_from: Arc<LastOpenTimes>,
 }
 
 impl Handler<DownloadCandlesticks> for CandlesticksDownloaderActor {
     type Result = MessageResult<DownloadCandlesticks>;
 
     fn handle(&mut self, msg: DownloadCandlesticks, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.start_new_job(msg.windows(), ctx))
+    }
+}
+
+impl DownloadCandlesticks {
+    /// Windows of the filtered symbols, (symbol, timeframe) pairs are interleaved symbol by symbol,
+    /// so no timeframe has to wait until another one is downloaded for every symbol
+    pub fn windows(&self) -> Vec<CandlesticksWindow> {
+        self.symbols
+            .iter()
+            .filter(|symbol| (self.filter)(symbol))
+            .flat_map(|symbol| {
+                self.timeframes.iter().map(|(timeframe, start_date)| {
+                    let start_date = self
+                        .resume_from
+                        .get(&(symbol.clone(), timeframe.clone()))
+                        .filter(|last_open_time| *last_open_time > start_date)
+                        .unwrap_or(start_date);
+                    CandlesticksWindow::new(symbol.clone(), timeframe.clone(), *start_date, self.end_date)
+                })
+            })
+            .collect()
+    }
+}
+
+impl CandlesticksDownloaderActor {
+    pub(super) fn start_new_job(&mut self, windows: Vec<CandlesticksWindow>, ctx: &mut Context<Self>) -> Result<JobReceiver<WindowPage>, DownloadHistoryError> {
+        let windows = windows.into_iter().map(|window| (window.start, window)).collect();
+        self.enqueue(self.jobs.next_job_id(), windows, true, ctx)
+    }
+
+    /// Queues the job and starts it as soon as a slot is free, its candlesticks are streamed into a fresh channel.
+    /// With `register_job` the windows are recorded as the job plan before the first page is requested.
+    pub(super) fn enqueue(
+        &mut self,
+        job_id: JobId,
+        windows: Vec<PlannedWindow>,
+        register_job: bool,
+        ctx: &mut Context<Self>,
+    ) -> Result<JobReceiver<WindowPage>, DownloadHistoryError> {
+        if self.jobs.active().contains(&job_id) {
+            return Err(DownloadHistoryError::JobIsActive(job_id));
+        }
+
+        let (sender, receiver) = mpsc::channel::<WindowPage>(self.downstream_buffer);
+        let (planned_starts, windows): (Vec<_>, Vec<_>) = windows.into_iter().unzip();
+        let job = DownloadJob {
+            progress_sender: watch::Sender::new(DownloadProgress::new(job_id, &windows)),
+            windows: windows.into_iter().enumerate().collect(),
+            planned_starts,
+            register_job,
+            sender: Some(sender),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        self.jobs.push(job_id, job);
+        info!("[CandlesticksDownloaderActor] queued job {}", job_id);
+
+        self.schedule(ctx);
+        Ok(JobReceiver { job_id, receiver })
+    }
+
+    /// Starts queued jobs while there are free slots, stops the actor once a requested shutdown has nothing left to wait for
+    pub(super) fn schedule(&mut self, ctx: &mut Context<Self>) {
+        if self.shutdown_requested {
+            if self.jobs.is_idle() {
+                info!("[CandlesticksDownloaderActor] is completing it's work");
+                ctx.stop();
+            }
+            return;
+        }
+
+        while let Some((job_id, job)) = self.jobs.start_next() {
+            let Some(sender) = job.sender.clone() else {
+                job.progress_sender.send_modify(|progress| progress.close_unfinished(WindowState::Cancelled));
+                self.jobs.finish(job_id);
+                continue;
+            };
+            let windows = std::mem::take(&mut job.windows)
+                .into_iter()
+                .map(|(index, window)| (index, (job.planned_starts[index], window)))
+                .collect();
+            let checkpoints = self.checkpoints.clone().filter(|_| job.register_job);
+            let progress_sender = job.progress_sender.clone();
+            let cancelled = job.cancelled.clone();
+            // a restarted job keeps its original start
+            progress_sender.send_modify(|progress| {
+                progress.started_at.get_or_insert_with(OffsetDateTime::now_utc);
+            });
+
+            ctx.spawn(
+                catch_panic(self.download(job_id, windows, checkpoints, sender, progress_sender, cancelled))
+                    .into_actor(self)
+                    .map(move |result, act, ctx| match result {
+                        Ok(_) => {
+                            info!("[CandlesticksDownloaderActor] finished job {}", job_id);
+                            act.finish_job(job_id, ctx);
+                        }
+                        Err(panic) => {
+                            let reason = panic_message(panic.as_ref());
+                            match act.supervision.crashed(job_id, panic) {
+                                Some(backoff) => {
+                                    if let Some(job) = act.jobs.get_mut(job_id) {
+                                        job.windows = job.remaining_windows();
+                                        job.register_job = false;
+                                    }
+                                    ctx.run_later(backoff, move |act, ctx| act.restart_job(job_id, ctx));
+                                }
+                                None => {
+                                    if let Some(job) = act.jobs.get(job_id) {
+                                        let state = WindowState::Failed(DownloadWindowError::Crashed(reason).to_string());
+                                        job.progress_sender.send_modify(|progress| progress.close_unfinished(state));
+                                    }
+                                    act.finish_job(job_id, ctx);
+                                }
+                            }
+                        }
+                    }),
+            );
+        }
+    }
+
+    /// Closes the receiver of the job, forgets its restarts and starts the next queued one
+    fn finish_job(&mut self, job_id: JobId, ctx: &mut Context<Self>) {
+        if let Some(job) = self.jobs.get_mut(job_id) {
+            job.sender.take();
+        }
+        self.jobs.finish(job_id);
+        self.supervision.finished(&job_id);
+        self.schedule(ctx);
+    }
+
+    /// Queues a crashed job again with its remaining windows, unless it was cancelled while backing off
+    fn restart_job(&mut self, job_id: JobId, ctx: &mut Context<Self>) {
+        let Some(job) = self.jobs.get(job_id) else { return };
+        match job.sender.is_some() {
+            true => {
+                info!(
+                    "[CandlesticksDownloaderActor] is restarting job {} with {} remaining windows",
+                    job_id,
+                    job.windows.len()
+                );
+                self.jobs.requeue(job_id);
+                self.schedule(ctx);
+            }
+            false => {
+                job.progress_sender.send_modify(|progress| progress.close_unfinished(WindowState::Cancelled));
+                self.finish_job(job_id, ctx);
+            }
+        }
+    }
+
+    /// Processes the windows concurrently, jobs that may run together share the request pacing.
+    /// When there are fewer windows than `concurrency`, the spare slots fetch date shards of the same window.
+    fn download(
+        &self,
+        job_id: JobId,
+        windows: Vec<(usize, PlannedWindow)>,
+        checkpoints: Option<Arc<dyn DownloadCheckpointsService + Send + Sync>>,
+        candlestick_sender: mpsc::Sender<WindowPage>,
+        progress_sender: watch::Sender<DownloadProgress>,
+        cancelled: Arc<AtomicBool>,
+    ) -> impl Future<Output = ()> + 'static {
         let binance_client = self.binance_client.clone();
         let concurrency = self.concurrency;
-        let rate_limit = self.binance_rate_limit;
-        let max_delay = rate_limit * self.concurrency;
+        let concurrency_controller = self.concurrency_controller.clone();
+        let request_pacer = self.request_pacer.clone();
         let buffer = self.downstream_buffer;
+        let retry_policy = self.retry_policy.clone();
+        let partial_candle_policy = self.partial_candle_policy;
+        let shard_pages = self.shard_pages;
+        let listing_dates = self.listing_dates.clone();
 
-        let timeframe = msg.timeframe.clone();
-        let symbols = msg.symbols.clone();
-        let start_date = msg.start_date;
-        let filter = msg.filter.clone();
+        async move {
+            if let Some(checkpoints) = checkpoints {
+                let _ = checkpoints
+                    .create_job(job_id, windows.iter().map(|(_, (_, window))| window.clone()).collect())
+                    .await
+                    .inspect(|_| info!("[CandlesticksDownloaderActor] registered job {} with {} windows", job_id, windows.len()))
+                    .inspect_err(|err| {
+                        error!(
+                            "[CandlesticksDownloaderActor] failed to register job {}, it won't be resumable: {}",
+                            job_id, err
+                        )
+                    });
+            }
 
-        match &self.status {
-            Ready => {
-                let (candlestick_sender, candlestick_receiver) = mpsc::channel::<Vec<Candlestick>>(buffer);
+            // pages are tagged with the planned start of their window, trimming doesn't change it
+            let (planned_starts, windows): (HashMap<_, _>, Vec<_>) = windows
+                .into_iter()
+                .map(|(index, (planned_start, window))| ((index, planned_start), (index, window)))
+                .unzip();
+            let planned_starts = Arc::new(planned_starts);
+            // a restarted job only has its remaining windows, they are still numbered after the whole job
+            let windows_count = progress_sender.borrow().windows.len();
+            // the controller decides how many of the streams request a page at a time
+            let max_concurrency = concurrency_controller.max();
+            let parallel_shards = (max_concurrency / windows.len().max(1)).max(1);
+            let windows = trim_to_listing_dates(
+                binance_client.clone(),
+                listing_dates,
+                windows,
+                concurrency,
+                request_pacer.clone(),
+                progress_sender.clone(),
+                cancelled.clone(),
+            )
+            .await;
+
+            stream::iter(windows)
+                .for_each_concurrent(max_concurrency, move |(index, window)| {
+                    let candlestick_sender = candlestick_sender.clone();
+                    let progress_sender = progress_sender.clone();
+                    let binance_client = binance_client.clone();
+                    let cancelled = cancelled.clone();
+                    let retry_policy = retry_policy.clone();
+                    let concurrency_controller = concurrency_controller.clone();
+                    let request_pacer = request_pacer.clone();
+                    let planned_starts = planned_starts.clone();
 
-                ctx.spawn(
                     async move {
-                        let symbols = symbols.clone();
-                        let candlestick_sender = candlestick_sender.clone();
-                        let binance_client = binance_client.clone();
-
-                        let symbol_list: Vec<Arc<Symbol>> = symbols.iter().filter(|symbol| filter(symbol)).cloned().collect();
-                        let symbols_count = symbol_list.len();
-
-                        stream::iter(symbol_list)
-                            .enumerate()
-                            .for_each_concurrent(concurrency, move |(index, symbol)| {
-                                info!(
-                                    "[CandlesticksDownloaderActor] is processing ({}/{}): {:?}",
-                                    index + 1,
-                                    symbols_count,
-                                    symbol.short_name()
-                                );
+                        if cancelled.load(Ordering::SeqCst) {
+                            progress_sender.send_modify(|progress| progress.set_state(index, WindowState::Cancelled));
+                            return;
+                        }
+                        // nothing is listed within the window, it is already reported as skipped
+                        let Some(window) = window else { return };
+                        let planned_start = planned_starts[&index];
+
+                        info!("[CandlesticksDownloaderActor] is processing ({}/{}): {}", index + 1, windows_count, window);
+                        progress_sender.send_modify(|progress| progress.set_state(index, WindowState::Running));
+                        let page_progress_sender = progress_sender.clone();
+                        let description = window.to_string();
+
+                        let fetcher = Fetcher {
+                            binance_client,
+                            retry_policy,
+                            request_pacer,
+                            concurrency_controller: concurrency_controller.clone(),
+                            cancelled: cancelled.clone(),
+                        };
+                        let result = stream_shards(fetcher, window, shard_pages, parallel_shards)
+                            .map_err(DownloadWindowError::from)
+                            .map_ok(move |(candlesticks, report)| (partial_candle_policy.apply(candlesticks), report))
+                            .and_then(move |(candlesticks, report)| {
                                 let candlestick_sender = candlestick_sender.clone();
+                                let capacity = candlestick_sender.capacity();
+                                concurrency_controller.record_downstream(capacity, buffer);
+
+                                if capacity * 10 < buffer * 3 {
+                                    warn!("[CandlesticksDownloaderActor] sender capacity is: {}; downstream is slow!", capacity);
+                                } else if capacity * 2 < buffer {
+                                    info!("[CandlesticksDownloaderActor] sender capacity is: {}; downstream is slow!", capacity);
+                                }
+
+                                let page_progress_sender = page_progress_sender.clone();
+                                let concurrency = concurrency_controller.limit();
 
-                                stream_candlesticks_by_symbol(binance_client.clone(), symbol.clone(), timeframe.clone(), start_date)
-                                    .then(move |(candlesticks, report)| {
-                                        let candlestick_sender = candlestick_sender.clone();
-                                        let capacity = candlestick_sender.capacity();
-
-                                        if capacity < (buffer * 0.3 as usize) {
-                                            warn!("[CandlesticksDownloaderActor] sender capacity is: {}; downstream is slow!", capacity);
-                                        } else if capacity < (buffer * 0.5 as usize) {
-                                            info!("[CandlesticksDownloaderActor] sender capacity is: {}; downstream is slow!", capacity);
-                                        }
-
-                                        async move {
-                                            candlestick_sender
-                                                .send(candlesticks)
-                                                .await
-                                                .inspect_err(|err| panic!("Candlesticks channel is closed: {err}"))
-                                                .map(|_| report)
-                                                .unwrap()
-                                        }
-                                    })
-                                    .for_each(move |report| async move {
-                                        let delay = (max_delay as u64).saturating_sub(report.latency as u64);
-                                        sleep(Duration::from_millis(delay)).await;
-                                    })
+                                // only pages that made it downstream count, a restarted job continues after them
+                                async move {
+                                    let (count, last_fetched) = (candlesticks.len(), candlesticks.last().map(|last| last.open_time));
+                                    candlestick_sender
+                                        .send((planned_start, candlesticks))
+                                        .await
+                                        .map_err(|_| DownloadWindowError::DownstreamClosed)?;
+                                    page_progress_sender.send_modify(|progress| {
+                                        progress.record_page(index, count, last_fetched, report.used_weight_1m);
+                                        progress.concurrency = concurrency;
+                                    });
+                                    Ok(report)
+                                }
                             })
+                            .try_for_each(|_| future::ready(Ok(())))
                             .await;
-                    }
-                    .into_actor(self)
-                    .map(|_, act, _ctx| {
-                        act.status = Ready;
-                        info!("[CandlesticksDownloaderActor] is ready to work");
-                    }),
-                );
 
-                self.status = Status::Busy;
-                MessageResult(Ok(candlestick_receiver))
-            }
-            _ => MessageResult(Err(DownloadHistoryError::ActorIsBusy)),
+                        let state = match result {
+                            Err(err) => {
+                                error!("[CandlesticksDownloaderActor] gave up on {}: {}", description, err);
+                                // nobody is listening anymore, there is no point in fetching the other windows
+                                if matches!(err, DownloadWindowError::DownstreamClosed) {
+                                    cancelled.store(true, Ordering::SeqCst);
+                                }
+                                WindowState::Failed(err.to_string())
+                            }
+                            Ok(_) if cancelled.load(Ordering::SeqCst) => WindowState::Cancelled,
+                            Ok(_) => WindowState::Done,
+                        };
+                        progress_sender.send_modify(|progress| progress.set_state(index, state));
+                    }
+                })
+                .await;
         }
     }
 }
 
-fn stream_candlesticks_by_symbol(
+/// Trims every window to the listing date of its symbol, windows with nothing listed become `None`.
+/// Windows whose listing date can't be discovered are kept as they are, paging finds the first candlestick anyway.
+async fn trim_to_listing_dates(
     binance_client: Arc<dyn KlinesApi + Send + Sync>,
-    symbol: Arc<Symbol>,
-    timeframe: Timeframe,
-    start_date: OffsetDateTime,
-) -> impl Stream<Item = (Vec<Candlestick>, FetchReport)> {
-    stream::unfold(Some(start_date), move |next_date| {
-        let binance_client = binance_client.clone();
-        let symbol = symbol.clone();
-        let timeframe = timeframe.clone();
+    listing_dates: ListingDates,
+    windows: Vec<(usize, CandlesticksWindow)>,
+    concurrency: usize,
+    request_pacer: RequestPacer,
+    progress_sender: watch::Sender<DownloadProgress>,
+    cancelled: Arc<AtomicBool>,
+) -> Vec<(usize, Option<CandlesticksWindow>)> {
+    stream::iter(windows)
+        .map(|(index, window)| {
+            let binance_client = binance_client.clone();
+            let listing_dates = listing_dates.clone();
+            let request_pacer = request_pacer.clone();
+            let progress_sender = progress_sender.clone();
+            let cancelled = cancelled.load(Ordering::SeqCst);
+
+            async move {
+                if cancelled {
+                    return (index, Some(window));
+                }
+
+                // a lookup is a request to the exchange like any page
+                if listing_dates.get(&window.symbol, &window.timeframe).is_none() {
+                    request_pacer.wait().await;
+                }
+                let listed_at = listing_dates.discover(binance_client, window.symbol.clone(), window.timeframe.clone()).await;
+
+                match listed_at {
+                    Ok(listed_at) => {
+                        let window = trim_to_listing(window, listed_at);
+                        progress_sender.send_modify(|progress| progress.trim_window(index, window.clone()));
+                        (index, window)
+                    }
+                    Err(err) => {
+                        warn!("[CandlesticksDownloaderActor] failed to discover the listing date of {}: {}", window, err);
+                        (index, Some(window))
+                    }
+                }
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await
+}
+
+/// What every page request of a job needs, cloned into each shard
+#[derive(Clone)]
+pub(crate) struct Fetcher {
+    pub(crate) binance_client: Arc<dyn KlinesApi + Send + Sync>,
+    pub(crate) retry_policy: RetryPolicy,
+    /// Shared by every fetcher of the actor, whatever job it belongs to
+    pub(crate) request_pacer: RequestPacer,
+    pub(crate) concurrency_controller: ConcurrencyController,
+    pub(crate) cancelled: Arc<AtomicBool>,
+}
+
+/// Fetches the date shards of the window `parallel_shards` at a time and emits their pages in date order.
+/// A shard is emitted once all of its pages are fetched. Shards after the first incomplete one are dropped,
+/// so the emitted candlesticks always run contiguously from the window start and checkpoints never skip a gap.
+pub(crate) fn stream_shards(
+    fetcher: Fetcher,
+    window: CandlesticksWindow,
+    shard_pages: usize,
+    parallel_shards: usize,
+) -> impl Stream<Item = Result<(Vec<Candlestick>, FetchReport), HttpResponseError>> {
+    stream::iter(split_into_shards(window, shard_pages, OffsetDateTime::now_utc()))
+        .map(move |shard| {
+            let cancelled = fetcher.cancelled.clone();
+            let pages = stream_candlesticks_by_symbol(fetcher.clone(), shard);
+            async move {
+                let pages = pages.collect::<Vec<_>>().await;
+                let complete = !cancelled.load(Ordering::SeqCst) && pages.iter().all(Result::is_ok);
+                (pages, complete)
+            }
+        })
+        .buffered(parallel_shards)
+        .scan(true, |previous_complete, (pages, complete)| {
+            let emit = std::mem::replace(previous_complete, complete);
+            future::ready(emit.then(|| stream::iter(pages)))
+        })
+        .flatten()
+}
+
+/// Pages through the window, a raised `cancelled` flag ends the stream before the next page is requested.
+/// Every page is retried according to `retry_policy`, the stream ends with the error once retries are exhausted.
+/// A page holds a slot of the concurrency controller until its response, every attempt also waits for its turn
+/// of the request pacer: the controller decides how many requests are in flight, the pacer how often they are sent.
+fn stream_candlesticks_by_symbol(
+    fetcher: Fetcher,
+    window: CandlesticksWindow,
+) -> impl Stream<Item = Result<(Vec<Candlestick>, FetchReport), HttpResponseError>> {
+    stream::unfold(Some(window.start), move |next_date| {
+        let Fetcher {
+            binance_client,
+            retry_policy,
+            request_pacer,
+            concurrency_controller,
+            cancelled,
+        } = fetcher.clone();
+        let symbol = window.symbol.clone();
+        let timeframe = window.timeframe.clone();
+        let end_date = window.end;
+        let cancelled = cancelled.load(Ordering::SeqCst);
         async move {
             match next_date {
-                Some(next_date) => {
-                    let (candlesticks, report) = fetch_next_candlesticks(binance_client.clone(), symbol.clone(), timeframe, next_date).await;
-                    let next_date = report.last_element_date;
-                    (report.produced_count != 0).then_some(((candlesticks, report), next_date))
+                _ if cancelled => None,
+                Some(next_date) if end_date.is_none_or(|end_date| next_date <= end_date) => {
+                    let permit = concurrency_controller.acquire().await;
+                    let attempt = || async {
+                        request_pacer.wait().await;
+                        fetch_next_candlesticks(binance_client.clone(), symbol.clone(), timeframe.clone(), next_date, end_date)
+                            .inspect_err(|_| concurrency_controller.record_error())
+                            .await
+                    };
+                    let result = Retry::start(retry_policy.strategy(), attempt).await;
+                    if let Ok((_, report)) = &result {
+                        concurrency_controller.record_response(report.latency, report.used_weight_1m);
+                    }
+                    drop(permit);
+
+                    match result {
+                        Ok((candlesticks, report)) => {
+                            let next_date = report.last_element_date;
+                            (report.produced_count != 0).then_some((Ok((candlesticks, report)), next_date))
+                        }
+                        Err(err) => Some((Err(err), None)),
+                    }
                 }
-                None => None,
+                _ => None,
             }
         }
     })
@@ -133,22 +480,26 @@ pub async fn fetch_next_candlesticks(
     symbol: Arc<Symbol>,
     timeframe: Timeframe,
     start_date: OffsetDateTime,
-) -> (Vec<Candlestick>, FetchReport) {
+    end_date: Option<OffsetDateTime>,
+) -> Result<(Vec<Candlestick>, FetchReport), HttpResponseError> {
     let timer = Instant::now();
 
-    let (candlesticks, _headers) = binance_client
-        .fetch_candlesticks(symbol.clone(), timeframe.clone(), 1000, Some(start_date), None)
+    let (candlesticks, headers) = binance_client
+        .fetch_candlesticks(symbol.clone(), timeframe.clone(), PAGE_SIZE, Some(start_date), end_date)
         .await
-        .inspect_err(|err| error!("Failed to fetch candlesticks ({:?}, {:?}, {:?}): {:?}", symbol, timeframe, start_date, err))
-        .expect("Error fetching binance");
+        .inspect_err(|err| warn!("Failed to fetch candlesticks ({:?}, {:?}, {:?}): {:?}", symbol, timeframe, start_date, err))?;
 
     let report = FetchReport {
         latency: timer.elapsed().as_millis() as u16,
         produced_count: candlesticks.len(),
         last_element_date: candlesticks.last().map(|last| last.close_time),
+        used_weight_1m: headers
+            .get(BINANCE_HEADER_USED_WEIGHT_1M)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok()),
     };
 
-    (candlesticks, report)
+    Ok((candlesticks, report))
 }
 
 #[derive(Debug)]
@@ -156,4 +507,5 @@ pub struct FetchReport {
     pub latency: u16,
     pub produced_count: usize,
     pub last_element_date: Option<OffsetDateTime>,
+    pub used_weight_1m: Option<u32>,
 }
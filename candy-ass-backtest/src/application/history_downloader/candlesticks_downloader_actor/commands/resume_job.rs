@@ -0,0 +1,41 @@
+use crate::application::history_downloader::candlesticks_downloader_actor::errors::DownloadHistoryError;
+use crate::application::history_downloader::candlesticks_downloader_actor::{CandlesticksDownloaderActor, WindowPage};
+use crate::application::job_queue::JobReceiver;
+use crate::integrations::clickhouse::candlesticks_repository::JobId;
+use actix::{ActorFutureExt, Handler, Message, ResponseActFuture, WrapFuture, fut};
+use tracing::info;
+
+/// Continues a registered job right after the last checkpoint of every window
+#[derive(Message, Clone)]
+#[rtype(result = "Result<JobReceiver<WindowPage>, DownloadHistoryError>")]
+pub struct ResumeJob {
+    pub job_id: JobId,
+}
+
+impl Handler<ResumeJob> for CandlesticksDownloaderActor {
+    type Result = ResponseActFuture<Self, Result<JobReceiver<WindowPage>, DownloadHistoryError>>;
+
+    fn handle(&mut self, msg: ResumeJob, _ctx: &mut Self::Context) -> Self::Result {
+        let Some(checkpoints) = self.checkpoints.clone() else {
+            return Box::pin(fut::ready(Err(DownloadHistoryError::CheckpointsAreDisabled)));
+        };
+        let job_id = msg.job_id;
+        if self.jobs.active().contains(&job_id) {
+            return Box::pin(fut::ready(Err(DownloadHistoryError::JobIsActive(job_id))));
+        }
+
+        Box::pin(
+            async move { checkpoints.fetch_remaining_windows(job_id).await }
+                .into_actor(self)
+                .map(move |windows, act, ctx| {
+                    let windows = windows.map_err(|err| DownloadHistoryError::FailedToLoadJob(err.to_string()))?;
+                    info!(
+                        "[CandlesticksDownloaderActor] is resuming job {} with {} remaining windows",
+                        job_id,
+                        windows.len()
+                    );
+                    act.enqueue(job_id, windows, false, ctx)
+                }),
+        )
+    }
+}
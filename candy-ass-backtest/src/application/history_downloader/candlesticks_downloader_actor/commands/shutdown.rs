@@ -1,5 +1,5 @@
 use crate::application::history_downloader::candlesticks_downloader_actor::CandlesticksDownloaderActor;
-use actix::{ActorContext, Handler, Message};
+use actix::{Handler, Message};
 use tracing::info;
 
 #[derive(Message)]
@@ -13,9 +13,13 @@ impl Handler<Command> for CandlesticksDownloaderActor {
 
     fn handle(&mut self, msg: Command, ctx: &mut Self::Context) -> Self::Result {
         match msg {
+            // queued jobs are dropped and running ones cancelled, the actor stops once they have flushed their pages downstream
             Command::Shutdown => {
-                info!("[CandlesticksDownloaderActor] is completing it's work");
-                ctx.stop();
+                if self.cancel_jobs(None) {
+                    info!("[CandlesticksDownloaderActor] cancelled its jobs before shutdown");
+                }
+                self.shutdown_requested = true;
+                self.schedule(ctx);
             }
         }
     }
@@ -0,0 +1,239 @@
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::{Instant, sleep_until};
+
+/// Binance allows 6000 weight per minute, the limit backs off well before requests get rejected
+pub const USED_WEIGHT_BACKOFF: u32 = 4800;
+/// Share of the downstream buffer below which the storage is considered slower than the exchange
+const DOWNSTREAM_BACKOFF: f64 = 0.3;
+/// Latency above `LATENCY_BACKOFF` times the best smoothed latency means the exchange is struggling
+const LATENCY_BACKOFF: f64 = 2.0;
+/// Latencies below it are never considered inflated, short requests are too noisy
+const LATENCY_FLOOR_MS: f64 = 50.0;
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// AIMD limit of the page requests in flight, shared by every job of the actor.
+/// Every healthy response grows the limit by `1 / limit`, i.e. by one per round of requests,
+/// errors, high used weight, inflated latency or a slow downstream halve it once per round.
+/// It only bounds the requests in flight, how often they are sent is up to the [`RequestPacer`].
+///
+/// The unit is a page request rather than a symbol stream: the shards of a window page in parallel,
+/// so a symbol may have several requests in flight, and a stream holds no slot while it waits for the downstream.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyController {
+    state: Arc<Mutex<State>>,
+    released: Arc<Notify>,
+}
+
+#[derive(Debug)]
+struct State {
+    limit: f64,
+    max: usize,
+    in_flight: usize,
+    /// Samples since the last decrease, the in-flight requests of the previous round can't trigger another one
+    since_decrease: usize,
+    smoothed_latency: Option<f64>,
+    best_latency: Option<f64>,
+}
+
+impl State {
+    fn limit(&self) -> usize {
+        self.limit as usize
+    }
+
+    fn increase(&mut self) {
+        self.since_decrease += 1;
+        self.limit = (self.limit + 1.0 / self.limit).min(self.max as f64);
+    }
+
+    fn decrease(&mut self) {
+        if self.since_decrease >= self.limit() {
+            self.limit = (self.limit / 2.0).max(1.0);
+            self.since_decrease = 0;
+        } else {
+            self.since_decrease += 1;
+        }
+    }
+}
+
+/// Slot of a request in flight, it is freed on drop
+pub struct ConcurrencyPermit {
+    controller: ConcurrencyController,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.controller.state.lock().unwrap().in_flight -= 1;
+        self.controller.released.notify_waiters();
+    }
+}
+
+impl ConcurrencyController {
+    pub fn new(initial: usize, max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            state: Arc::new(Mutex::new(State {
+                limit: initial.clamp(1, max) as f64,
+                max,
+                in_flight: 0,
+                since_decrease: 0,
+                smoothed_latency: None,
+                best_latency: None,
+            })),
+            released: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit()
+    }
+
+    pub fn max(&self) -> usize {
+        self.state.lock().unwrap().max
+    }
+
+    /// Waits until a request fits into the current limit
+    pub async fn acquire(&self) -> ConcurrencyPermit {
+        loop {
+            let mut released = pin!(self.released.notified());
+            released.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.in_flight < state.limit() {
+                    state.in_flight += 1;
+                    return ConcurrencyPermit { controller: self.clone() };
+                }
+            }
+            released.await;
+        }
+    }
+
+    /// A failed attempt, e.g. a timeout or a rejected request that is going to be retried
+    pub fn record_error(&self) {
+        self.state.lock().unwrap().decrease();
+    }
+
+    pub fn record_response(&self, latency_ms: u16, used_weight_1m: Option<u32>) {
+        let mut state = self.state.lock().unwrap();
+        let latency = latency_ms as f64;
+        let smoothed = state
+            .smoothed_latency
+            .map_or(latency, |smoothed| smoothed + LATENCY_SMOOTHING * (latency - smoothed));
+        let best = state.best_latency.map_or(smoothed, |best| best.min(smoothed));
+        state.smoothed_latency = Some(smoothed);
+        state.best_latency = Some(best);
+
+        let overloaded = used_weight_1m.is_some_and(|weight| weight >= USED_WEIGHT_BACKOFF);
+        let slow = smoothed > LATENCY_FLOOR_MS && smoothed > best * LATENCY_BACKOFF;
+        match overloaded || slow {
+            true => state.decrease(),
+            false => state.increase(),
+        }
+        drop(state);
+        self.released.notify_waiters();
+    }
+
+    /// Free capacity of the channel the pages are sent into, a full channel means the storage can't keep up
+    pub fn record_downstream(&self, capacity: usize, buffer: usize) {
+        if (capacity as f64) < buffer as f64 * DOWNSTREAM_BACKOFF {
+            self.state.lock().unwrap().decrease();
+        }
+    }
+}
+
+/// Spaces the requests of every job of the actor `interval` apart, whatever the number of requests in flight.
+/// Requests take the next free slot in turn, so a burst is spread out instead of being sent at once.
+#[derive(Debug, Clone)]
+pub struct RequestPacer {
+    interval: Duration,
+    next_slot: Arc<Mutex<Instant>>,
+}
+
+impl RequestPacer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_slot: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Waits for the slot of the next request
+    pub async fn wait(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future::join_all;
+
+    #[test]
+    fn test_limit_grows_additively_up_to_max() {
+        let controller = ConcurrencyController::new(2, 3);
+        (0..2).for_each(|_| controller.record_response(20, Some(10)));
+        assert_eq!(2, controller.limit());
+        controller.record_response(20, Some(10));
+        assert_eq!(3, controller.limit());
+
+        (0..10).for_each(|_| controller.record_response(20, Some(10)));
+        assert_eq!(3, controller.limit());
+    }
+
+    #[test]
+    fn test_limit_is_halved_once_per_round() {
+        let controller = ConcurrencyController::new(8, 8);
+        (0..8).for_each(|_| controller.record_response(20, None));
+
+        controller.record_error();
+        assert_eq!(4, controller.limit());
+        // the rest of the round was already in flight
+        (0..4).for_each(|_| controller.record_error());
+        assert_eq!(4, controller.limit());
+
+        controller.record_response(20, Some(USED_WEIGHT_BACKOFF));
+        assert_eq!(2, controller.limit());
+        (0..10).for_each(|_| controller.record_downstream(0, 10));
+        assert_eq!(1, controller.limit());
+    }
+
+    #[test]
+    fn test_inflated_latency_backs_off() {
+        let controller = ConcurrencyController::new(4, 4);
+        (0..4).for_each(|_| controller.record_response(100, None));
+        (0..4).for_each(|_| controller.record_response(2000, None));
+
+        assert!(controller.limit() < 4);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_a_free_slot() {
+        let controller = ConcurrencyController::new(1, 1);
+        let permit = controller.acquire().await;
+
+        let waiting = tokio::time::timeout(Duration::from_millis(10), controller.acquire()).await;
+        assert!(waiting.is_err());
+
+        drop(permit);
+        let acquired = tokio::time::timeout(Duration::from_millis(10), controller.acquire()).await;
+        assert!(acquired.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pacer_spreads_a_burst() {
+        let pacer = RequestPacer::new(Duration::from_millis(10));
+        let start = Instant::now();
+
+        join_all((0..5).map(|_| pacer.wait())).await;
+
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+}
@@ -1,4 +1,24 @@
+use crate::integrations::clickhouse::candlesticks_repository::JobId;
+use candy_ass_core::integrations::http::HttpResponseError;
+use thiserror::Error;
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum DownloadHistoryError {
-    ActorIsBusy,
+    CheckpointsAreDisabled,
+    FailedToLoadJob(String),
+    /// The job is already queued or running, it can't be resumed twice
+    JobIsActive(JobId),
+}
+
+/// Reason a single window was given up, the rest of the job keeps running
+#[derive(Debug, Error)]
+pub enum DownloadWindowError {
+    #[error("Request failed after retries: {0}")]
+    Transport(#[from] HttpResponseError),
+
+    #[error("Candlesticks channel is closed")]
+    DownstreamClosed,
+
+    #[error("Download crashed and wasn't restarted: {0}")]
+    Crashed(String),
 }
@@ -0,0 +1,113 @@
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use crate::integrations::clickhouse::candlesticks_repository::{KnownListingDates, ListingDatesService};
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::domain::symbol::Symbol;
+use candy_ass_core::domain::timeframe::Timeframe;
+use candy_ass_core::integrations::http::HttpResponseError;
+use candy_ass_core::integrations::http::binance::spot_http_client::KlinesApi;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use time::OffsetDateTime;
+use tracing::error;
+
+type ListingDatesCache = HashMap<(Arc<Symbol>, Timeframe), OffsetDateTime>;
+
+/// `open_time` of the first available candlestick per (symbol, timeframe).
+/// Symbols the exchange has no candlestick for yet are not cached, every job looks them up again until they start trading.
+/// With a store the discovered dates outlive the run.
+#[derive(Clone, Default)]
+pub struct ListingDates {
+    cache: Arc<RwLock<ListingDatesCache>>,
+    store: Option<Arc<dyn ListingDatesService + Send + Sync>>,
+}
+
+impl ListingDates {
+    pub fn with_store(mut self, store: Arc<dyn ListingDatesService + Send + Sync>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn is_persisted(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Caches the dates persisted by previous runs and returns how many there are
+    pub async fn load(&self) -> Result<usize, ClickhouseRepositoryError> {
+        let Some(store) = &self.store else { return Ok(0) };
+        let persisted = store.fetch_listing_dates().await?;
+        let count = persisted.len();
+        let mut cache = self.cache.write().unwrap();
+        cache.extend(persisted);
+        Ok(count)
+    }
+
+    pub fn get(&self, symbol: &Arc<Symbol>, timeframe: &Timeframe) -> Option<OffsetDateTime> {
+        self.cache.read().unwrap().get(&(symbol.clone(), timeframe.clone())).copied()
+    }
+
+    /// Caches a known listing date, e.g. one discovered by another process
+    pub fn insert(&self, symbol: Arc<Symbol>, timeframe: Timeframe, listed_at: OffsetDateTime) {
+        self.cache.write().unwrap().insert((symbol, timeframe), listed_at);
+    }
+
+    /// Served from the cache, otherwise asks the exchange for a single candlestick since the epoch.
+    /// `None` when the exchange has none yet
+    pub async fn discover(
+        &self,
+        binance_client: Arc<dyn KlinesApi + Send + Sync>,
+        symbol: Arc<Symbol>,
+        timeframe: Timeframe,
+    ) -> Result<Option<OffsetDateTime>, HttpResponseError> {
+        if let Some(listed_at) = self.get(&symbol, &timeframe) {
+            return Ok(Some(listed_at));
+        }
+
+        let (candlesticks, _) = binance_client
+            .fetch_candlesticks(symbol.clone(), timeframe.clone(), 1, Some(OffsetDateTime::UNIX_EPOCH), None)
+            .await?;
+        let Some(listed_at) = candlesticks.first().map(|first| first.open_time) else {
+            return Ok(None);
+        };
+        self.insert(symbol.clone(), timeframe.clone(), listed_at);
+
+        // the date is cached either way, a failed save only costs a lookup next run
+        if let Some(store) = &self.store {
+            let _ = store
+                .save_listing_dates(KnownListingDates::from([((symbol.clone(), timeframe), listed_at)]))
+                .await
+                .inspect_err(|err| error!("Failed to save the listing date of {}: {}", symbol.short_name(), err));
+        }
+        Ok(Some(listed_at))
+    }
+}
+
+/// Part of the window since the listing date, `None` when nothing is listed within the window
+pub fn trim_to_listing(window: CandlesticksWindow, listed_at: Option<OffsetDateTime>) -> Option<CandlesticksWindow> {
+    let start = window.start.max(listed_at?);
+    window.end.is_none_or(|end| start <= end).then_some(CandlesticksWindow { start, ..window })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::timeframe::Timeframe::OneHour;
+    use time::Duration;
+
+    #[test]
+    fn test_trim_to_listing() {
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let start = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+        let window = CandlesticksWindow::new(btc_usdt, OneHour, start, Some(start + Duration::days(10)));
+
+        let listed_later = trim_to_listing(window.clone(), Some(start + Duration::days(2))).unwrap();
+        assert_eq!(start + Duration::days(2), listed_later.start);
+        assert_eq!(window.end, listed_later.end);
+
+        let listed_earlier = trim_to_listing(window.clone(), Some(start - Duration::days(2))).unwrap();
+        assert_eq!(start, listed_earlier.start);
+
+        assert!(trim_to_listing(window.clone(), Some(start + Duration::days(11))).is_none());
+        assert!(trim_to_listing(window, None).is_none());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::application::history_downloader::candlesticks_downloader_actor::listing_dates::{ListingDates, trim_to_listing};
+use crate::application::history_downloader::candlesticks_downloader_actor::shards::PAGE_SIZE;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::domain::symbol::Symbol;
+use candy_ass_core::integrations::http::binance::BINANCE_KLINES_WEIGHT;
+use std::sync::Arc;
+use std::time::Duration;
+use time::OffsetDateTime;
+
+/// Estimated cost of a single window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowPlan {
+    /// Trimmed to the listing date when it is already known
+    pub window: CandlesticksWindow,
+    pub expected_candles: usize,
+    pub requests: usize,
+}
+
+impl WindowPlan {
+    /// Candlesticks follow the timeframe cadence, an open window takes one more request to find out it is over
+    pub fn new(window: CandlesticksWindow, now: OffsetDateTime) -> Self {
+        let end = window.end.unwrap_or(now);
+        let expected_candles = match end < window.start {
+            true => 0,
+            false => ((end - window.start).whole_milliseconds() / window.timeframe.duration().whole_milliseconds()) as usize + 1,
+        };
+        let requests = expected_candles.div_ceil(PAGE_SIZE as usize) + window.end.is_none() as usize;
+        Self {
+            window,
+            expected_candles,
+            requests,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolPlan {
+    pub symbol: Arc<Symbol>,
+    pub expected_candles: usize,
+    pub requests: usize,
+}
+
+/// What a download of the windows would cost, computed without fetching anything
+#[derive(Debug, Clone, Default)]
+pub struct DownloadPlan {
+    pub windows: Vec<WindowPlan>,
+    /// Nothing is listed within these windows according to the known listing dates
+    pub skipped: Vec<CandlesticksWindow>,
+    /// Windows whose listing date is not known yet, their cost is unknown until it is looked up when the job starts
+    pub unknown_listing: Vec<CandlesticksWindow>,
+    /// Listing dates that are not known yet, each of them takes a request before the window is fetched
+    pub listing_lookups: usize,
+    /// Klines requests including the listing lookups, the windows with an unknown listing date are left out
+    pub requests: usize,
+    pub weight: u64,
+    /// Time the requests take under the request pacing, slow responses make it longer
+    pub eta: Duration,
+}
+
+impl DownloadPlan {
+    /// `request_interval` is the pacing of the whole job, i.e. the time between two requests of its concurrent fetchers
+    pub fn new(windows: Vec<CandlesticksWindow>, listing_dates: &ListingDates, now: OffsetDateTime, request_interval: Duration) -> Self {
+        let mut plan = DownloadPlan::default();
+        for window in windows {
+            let Some(listed_at) = listing_dates.get(&window.symbol, &window.timeframe) else {
+                plan.listing_lookups += 1;
+                plan.unknown_listing.push(window);
+                continue;
+            };
+            match trim_to_listing(window.clone(), Some(listed_at)) {
+                Some(window) => plan.windows.push(WindowPlan::new(window, now)),
+                None => plan.skipped.push(window),
+            }
+        }
+
+        plan.requests = plan.listing_lookups + plan.windows.iter().map(|window| window.requests).sum::<usize>();
+        plan.weight = plan.requests as u64 * BINANCE_KLINES_WEIGHT as u64;
+        plan.eta = request_interval * plan.requests as u32;
+        plan
+    }
+
+    pub fn expected_candles(&self) -> usize {
+        self.windows.iter().map(|window| window.expected_candles).sum()
+    }
+
+    /// Symbols with a window whose listing date is not known yet, in the order of their windows
+    pub fn unknown_listing_symbols(&self) -> Vec<Arc<Symbol>> {
+        let mut symbols: Vec<Arc<Symbol>> = vec![];
+        for window in &self.unknown_listing {
+            if !symbols.contains(&window.symbol) {
+                symbols.push(window.symbol.clone());
+            }
+        }
+        symbols
+    }
+
+    /// Symbols with something to fetch, in the order of their windows
+    pub fn symbols(&self) -> Vec<SymbolPlan> {
+        let mut symbols: Vec<SymbolPlan> = vec![];
+        for window in &self.windows {
+            match symbols.iter_mut().find(|symbol| symbol.symbol == window.window.symbol) {
+                Some(symbol) => {
+                    symbol.expected_candles += window.expected_candles;
+                    symbol.requests += window.requests;
+                }
+                None => symbols.push(SymbolPlan {
+                    symbol: window.window.symbol.clone(),
+                    expected_candles: window.expected_candles,
+                    requests: window.requests,
+                }),
+            }
+        }
+        symbols
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::timeframe::Timeframe::{OneHour, OneMinute};
+
+    #[test]
+    fn test_window_plan() {
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let start = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+        let end = start + time::Duration::minutes(2500);
+
+        let bounded = WindowPlan::new(CandlesticksWindow::new(btc_usdt.clone(), OneMinute, start, Some(end)), start);
+        assert_eq!(2501, bounded.expected_candles);
+        assert_eq!(3, bounded.requests);
+
+        let open = WindowPlan::new(CandlesticksWindow::new(btc_usdt, OneMinute, start, None), end);
+        assert_eq!(2501, open.expected_candles);
+        assert_eq!(4, open.requests);
+    }
+
+    #[test]
+    fn test_download_plan() {
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let start = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+        let end = start + time::Duration::hours(1999);
+        let windows = vec![
+            CandlesticksWindow::new(btc_usdt.clone(), OneHour, start, Some(end)),
+            CandlesticksWindow::new(btc_usdt.clone(), OneMinute, start, Some(end)),
+            CandlesticksWindow::new(eth_usdt.clone(), OneHour, start, Some(end)),
+        ];
+
+        let listing_dates = ListingDates::default();
+        listing_dates.insert(btc_usdt.clone(), OneHour, start - time::Duration::days(1));
+        listing_dates.insert(btc_usdt.clone(), OneMinute, start);
+        listing_dates.insert(eth_usdt.clone(), OneHour, start);
+
+        let plan = DownloadPlan::new(windows, &listing_dates, start, Duration::from_millis(25));
+
+        assert_eq!(0, plan.listing_lookups);
+        assert_eq!(2000 + 119_941 + 2000, plan.expected_candles());
+        assert_eq!(2 + 120 + 2, plan.requests);
+        assert_eq!(2 * 124, plan.weight);
+        assert_eq!(Duration::from_millis(25 * 124), plan.eta);
+
+        let symbols = plan.symbols();
+        assert_eq!(2, symbols.len());
+        assert_eq!(btc_usdt, symbols[0].symbol);
+        assert_eq!(122, symbols[0].requests);
+    }
+
+    #[test]
+    fn test_unknown_listing_dates_are_reported() {
+        // given
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let start = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+        let end = start + time::Duration::hours(1999);
+        let windows = vec![
+            CandlesticksWindow::new(btc_usdt.clone(), OneHour, start, Some(end)),
+            CandlesticksWindow::new(eth_usdt.clone(), OneHour, start, Some(end)),
+            CandlesticksWindow::new(eth_usdt.clone(), OneMinute, start, Some(end)),
+        ];
+        let listing_dates = ListingDates::default();
+        listing_dates.insert(btc_usdt.clone(), OneHour, start);
+
+        // when
+        let plan = DownloadPlan::new(windows, &listing_dates, start, Duration::from_millis(25));
+
+        // then: the ETH windows are not assumed to run from the start date
+        assert_eq!(2, plan.listing_lookups);
+        assert_eq!(2, plan.unknown_listing.len());
+        assert_eq!(vec![eth_usdt], plan.unknown_listing_symbols());
+        assert_eq!(2000, plan.expected_candles());
+        assert_eq!(2 + 2, plan.requests);
+        assert_eq!(vec![btc_usdt], plan.symbols().into_iter().map(|symbol| symbol.symbol).collect::<Vec<_>>());
+    }
+}
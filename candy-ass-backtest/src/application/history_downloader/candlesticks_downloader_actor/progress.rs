@@ -0,0 +1,223 @@
+use crate::integrations::clickhouse::candlesticks_repository::JobId;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use time::{Duration, OffsetDateTime};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowState {
+    Pending,
+    Running,
+    Done,
+    /// Nothing is listed within the window, no page was requested
+    Skipped,
+    /// Stopped by a cancellation before reaching the end of the window
+    Cancelled,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowProgress {
+    pub window: CandlesticksWindow,
+    pub state: WindowState,
+    pub candles_fetched: usize,
+    /// `open_time` of the latest fetched candlestick
+    pub last_fetched: Option<OffsetDateTime>,
+}
+
+impl WindowProgress {
+    pub fn new(window: CandlesticksWindow) -> Self {
+        Self {
+            window,
+            state: WindowState::Pending,
+            candles_fetched: 0,
+            last_fetched: None,
+        }
+    }
+
+    /// Share of the window time range that is already fetched, in `[0, 1]`
+    pub fn completion(&self, now: OffsetDateTime) -> f64 {
+        match (&self.state, self.last_fetched) {
+            (WindowState::Done | WindowState::Skipped | WindowState::Failed(_), _) => 1.0,
+            (_, None) => 0.0,
+            (_, Some(last_fetched)) => {
+                let end = self.window.end.unwrap_or(now);
+                let total = (end - self.window.start).as_seconds_f64();
+                let fetched = (last_fetched - self.window.start).as_seconds_f64();
+                if total <= 0.0 { 1.0 } else { (fetched / total).clamp(0.0, 1.0) }
+            }
+        }
+    }
+
+    /// Number of candlesticks the window is expected to contain, used to weight windows against each other
+    pub fn expected_candles(&self, now: OffsetDateTime) -> f64 {
+        let end = self.window.end.unwrap_or(now);
+        let span = (end - self.window.start).as_seconds_f64();
+        (span / self.window.timeframe.duration().as_seconds_f64()).max(1.0)
+    }
+}
+
+/// Snapshot of a download job
+#[derive(Debug, Clone, Default)]
+pub struct DownloadProgress {
+    pub job_id: Option<JobId>,
+    pub windows: Vec<WindowProgress>,
+    /// `None` while the job is queued
+    pub started_at: Option<OffsetDateTime>,
+    /// Klines pages fetched so far
+    pub requests: usize,
+    /// Latest `x-mbx-used-weight-1m` reported by the exchange
+    pub used_weight_1m: Option<u32>,
+    /// Page requests the actor allowed in flight when the latest page was fetched
+    pub concurrency: usize,
+}
+
+impl DownloadProgress {
+    pub fn new(job_id: JobId, windows: &[CandlesticksWindow]) -> Self {
+        Self {
+            job_id: Some(job_id),
+            windows: windows.iter().cloned().map(WindowProgress::new).collect(),
+            started_at: None,
+            requests: 0,
+            used_weight_1m: None,
+            concurrency: 0,
+        }
+    }
+
+    pub fn set_state(&mut self, index: usize, state: WindowState) {
+        if let Some(window) = self.windows.get_mut(index) {
+            window.state = state;
+        }
+    }
+
+    /// Narrows the window down to its listed part, so completion and ETA only count what can be fetched
+    pub fn trim_window(&mut self, index: usize, window: Option<CandlesticksWindow>) {
+        if let Some(progress) = self.windows.get_mut(index) {
+            match window {
+                Some(window) => progress.window = window,
+                None => progress.state = WindowState::Skipped,
+            }
+        }
+    }
+
+    /// `last_fetched` is the `open_time` of the latest candlestick of the page, if any
+    pub fn record_page(&mut self, index: usize, candles_count: usize, last_fetched: Option<OffsetDateTime>, used_weight_1m: Option<u32>) {
+        self.requests += 1;
+        self.used_weight_1m = used_weight_1m.or(self.used_weight_1m);
+        if let Some(window) = self.windows.get_mut(index) {
+            window.candles_fetched += candles_count;
+            window.last_fetched = last_fetched.or(window.last_fetched);
+        }
+    }
+
+    /// Moves the pending and running windows to `state`, e.g. when the job won't continue them
+    pub fn close_unfinished(&mut self, state: WindowState) {
+        self.windows
+            .iter_mut()
+            .filter(|window| matches!(window.state, WindowState::Pending | WindowState::Running))
+            .for_each(|window| window.state = state.clone());
+    }
+
+    /// Windows that were given up, with the last error
+    pub fn failures(&self) -> Vec<(&CandlesticksWindow, &str)> {
+        self.windows
+            .iter()
+            .filter_map(|window| match &window.state {
+                WindowState::Failed(err) => Some((&window.window, err.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn candles_fetched(&self) -> usize {
+        self.windows.iter().map(|window| window.candles_fetched).sum()
+    }
+
+    pub fn count(&self, state: &WindowState) -> usize {
+        self.windows
+            .iter()
+            .filter(|window| std::mem::discriminant(&window.state) == std::mem::discriminant(state))
+            .count()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.windows.iter().all(|window| {
+            matches!(
+                window.state,
+                WindowState::Done | WindowState::Skipped | WindowState::Cancelled | WindowState::Failed(_)
+            )
+        })
+    }
+
+    /// Overall completion in `[0, 1]`, windows are weighted by their expected candlesticks count
+    pub fn completion(&self, now: OffsetDateTime) -> f64 {
+        let (done, total) = self.windows.iter().fold((0.0, 0.0), |(done, total), window| {
+            let weight = window.expected_candles(now);
+            (done + weight * window.completion(now), total + weight)
+        });
+        if total == 0.0 { 1.0 } else { done / total }
+    }
+
+    /// Remaining time extrapolated from the elapsed time and the overall completion
+    pub fn eta(&self, now: OffsetDateTime) -> Option<Duration> {
+        let elapsed = (now - self.started_at?).as_seconds_f64();
+        let completion = self.completion(now);
+        match completion {
+            c if c >= 1.0 => Some(Duration::ZERO),
+            c if c <= 0.0 => None,
+            c => Some(Duration::seconds_f64(elapsed * (1.0 - c) / c)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::symbol::Symbol;
+    use candy_ass_core::domain::timeframe::Timeframe;
+    use candy_ass_core::domain::timeframe::Timeframe::{OneDay, OneHour};
+    use time::format_description::well_known::Rfc3339;
+
+    fn window(timeframe: Timeframe, start: OffsetDateTime, end: OffsetDateTime) -> CandlesticksWindow {
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        CandlesticksWindow::new(btc_usdt, timeframe, start, Some(end))
+    }
+
+    #[test]
+    fn test_window_completion() {
+        let start = OffsetDateTime::parse("2024-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let mut progress = WindowProgress::new(window(OneHour, start, start + Duration::days(4)));
+        assert_eq!(0.0, progress.completion(start));
+
+        progress.state = WindowState::Running;
+        progress.last_fetched = Some(start + Duration::days(1));
+        assert_eq!(0.25, progress.completion(start));
+
+        progress.state = WindowState::Done;
+        assert_eq!(1.0, progress.completion(start));
+    }
+
+    #[test]
+    fn test_download_progress_eta() {
+        let start = OffsetDateTime::parse("2024-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let windows = vec![
+            window(OneHour, start, start + Duration::days(2)),
+            window(OneDay, start, start + Duration::days(2)),
+        ];
+        let mut progress = DownloadProgress::new(1, &windows);
+        assert_eq!(None, progress.eta(start));
+
+        progress.started_at = Some(start);
+        assert_eq!(None, progress.eta(start + Duration::minutes(1)));
+
+        progress.windows[0].state = WindowState::Running;
+        progress.windows[0].last_fetched = Some(start + Duration::days(1));
+
+        // 1h window dominates the weight: 48 of 50 expected candlesticks, half of them are fetched
+        let now = start + Duration::minutes(1);
+        assert_eq!(0.48, progress.completion(now));
+        assert_eq!(Some(65), progress.eta(now).map(|eta| eta.as_seconds_f64().round() as i64));
+        assert_eq!(1, progress.count(&WindowState::Running));
+        assert_eq!(1, progress.count(&WindowState::Pending));
+        assert!(!progress.is_finished());
+    }
+}
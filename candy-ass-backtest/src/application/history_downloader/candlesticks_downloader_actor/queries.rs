@@ -1 +1,97 @@
+use crate::application::history_downloader::candlesticks_downloader_actor::CandlesticksDownloaderActor;
+use crate::application::history_downloader::candlesticks_downloader_actor::plan::DownloadPlan;
+use crate::application::history_downloader::candlesticks_downloader_actor::progress::DownloadProgress;
+use crate::application::job_queue::JobSummary;
+use crate::integrations::clickhouse::candlesticks_repository::JobId;
+use actix::{Handler, Message, MessageResult, ResponseFuture};
+use candy_ass_core::application::supervision::LifecycleEvent;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::broadcast;
+use tokio::sync::watch::Receiver;
+use tracing::error;
 
+/// Progress of a queued, running or recently finished job
+#[derive(Message)]
+#[rtype(result = "Option<DownloadProgress>")]
+pub struct GetProgress(pub JobId);
+
+impl Handler<GetProgress> for CandlesticksDownloaderActor {
+    type Result = MessageResult<GetProgress>;
+    fn handle(&mut self, msg: GetProgress, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.jobs.get(msg.0).map(|job| job.progress_sender.borrow().clone()))
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Option<Receiver<DownloadProgress>>")]
+pub struct GetProgressReceiver(pub JobId);
+
+impl Handler<GetProgressReceiver> for CandlesticksDownloaderActor {
+    type Result = MessageResult<GetProgressReceiver>;
+    fn handle(&mut self, msg: GetProgressReceiver, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.jobs.get(msg.0).map(|job| job.progress_sender.subscribe()))
+    }
+}
+
+/// Queued, running and recently finished jobs, oldest first
+#[derive(Message)]
+#[rtype(result = "Vec<JobSummary<DownloadProgress>>")]
+pub struct ListJobs;
+
+impl Handler<ListJobs> for CandlesticksDownloaderActor {
+    type Result = MessageResult<ListJobs>;
+    fn handle(&mut self, _msg: ListJobs, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(
+            self.jobs
+                .list()
+                .map(|(job_id, status, job)| JobSummary {
+                    job_id,
+                    status,
+                    details: job.progress_sender.borrow().clone(),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Cost of downloading the windows as a new job, e.g. from [`DownloadCandlesticks::windows`], nothing is fetched from the exchange.
+/// The persisted listing dates are reloaded first, windows whose listing date is still unknown are reported apart.
+///
+/// [`DownloadCandlesticks::windows`]: crate::application::history_downloader::candlesticks_downloader_actor::commands::download_candlesticks::DownloadCandlesticks::windows
+#[derive(Message)]
+#[rtype(result = "DownloadPlan")]
+pub struct PlanDownload {
+    pub windows: Vec<CandlesticksWindow>,
+}
+
+impl Handler<PlanDownload> for CandlesticksDownloaderActor {
+    type Result = ResponseFuture<DownloadPlan>;
+    fn handle(&mut self, msg: PlanDownload, _ctx: &mut Self::Context) -> Self::Result {
+        // the pacing of the actor is shared by all the jobs that may run together
+        let request_interval = Duration::from_millis((self.binance_rate_limit * self.jobs.max_running()) as u64);
+        let listing_dates = self.listing_dates.clone();
+
+        Box::pin(async move {
+            // other runs may have discovered listing dates since the actor started
+            let _ = listing_dates
+                .load()
+                .await
+                .inspect_err(|err| error!("[CandlesticksDownloaderActor] failed to load the listing dates: {}", err));
+            DownloadPlan::new(msg.windows, &listing_dates, OffsetDateTime::now_utc(), request_interval)
+        })
+    }
+}
+
+/// Crashes, restarts and the stop of the actor, from the moment of the subscription
+#[derive(Message)]
+#[rtype(result = "broadcast::Receiver<LifecycleEvent>")]
+pub struct GetLifecycleReceiver;
+
+impl Handler<GetLifecycleReceiver> for CandlesticksDownloaderActor {
+    type Result = MessageResult<GetLifecycleReceiver>;
+    fn handle(&mut self, _msg: GetLifecycleReceiver, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.supervision.subscribe())
+    }
+}
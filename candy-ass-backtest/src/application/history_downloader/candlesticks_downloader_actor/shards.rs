@@ -0,0 +1,58 @@
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use time::{Duration, OffsetDateTime};
+
+/// Candlesticks served by a single klines request
+pub const PAGE_SIZE: u16 = 1000;
+
+/// Splits the window into consecutive date ranges of `pages_per_shard` pages each, so they can be fetched in parallel.
+/// An open window is split up to `now`, its last shard stays open.
+pub fn split_into_shards(window: CandlesticksWindow, pages_per_shard: usize, now: OffsetDateTime) -> Vec<CandlesticksWindow> {
+    let span = window.timeframe.duration() * (PAGE_SIZE as u32 * pages_per_shard.max(1) as u32);
+    let last = window.end.unwrap_or(now);
+
+    let mut shards = vec![];
+    let mut start = window.start;
+    while start + span <= last {
+        let next_start = start + span;
+        // both ends are inclusive, the next shard begins with the next candlestick
+        shards.push(CandlesticksWindow::new(
+            window.symbol.clone(),
+            window.timeframe.clone(),
+            start,
+            Some(next_start - Duration::milliseconds(1)),
+        ));
+        start = next_start;
+    }
+    shards.push(CandlesticksWindow { start, ..window });
+    shards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::symbol::Symbol;
+    use candy_ass_core::domain::timeframe::Timeframe::OneHour;
+
+    #[test]
+    fn test_split_into_shards() {
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let start = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+        let now = start + Duration::hours(2500);
+        let window = CandlesticksWindow::new(btc_usdt, OneHour, start, None);
+
+        let shards = split_into_shards(window.clone(), 1, now);
+        assert_eq!(3, shards.len());
+        assert_eq!(start, shards[0].start);
+        assert_eq!(Some(start + Duration::hours(1000) - Duration::milliseconds(1)), shards[0].end);
+        assert_eq!(start + Duration::hours(1000), shards[1].start);
+        assert_eq!(start + Duration::hours(2000), shards[2].start);
+        assert_eq!(None, shards[2].end);
+
+        let bounded = CandlesticksWindow {
+            end: Some(start + Duration::hours(10)),
+            ..window
+        };
+        assert_eq!(vec![bounded.clone()], split_into_shards(bounded, 1, now));
+    }
+}
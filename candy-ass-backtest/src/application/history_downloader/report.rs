@@ -0,0 +1,128 @@
+use crate::application::history_downloader::candlesticks_downloader_actor::progress::{DownloadProgress, WindowState};
+use crate::integrations::clickhouse::candlesticks_repository::JobId;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::domain::symbol::Symbol;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Summary of a finished pipeline run, e.g. for logs, exit codes and alerts
+#[derive(Debug, Clone, Default)]
+pub struct DownloadReport {
+    /// Latest job of the run
+    pub job_id: Option<JobId>,
+    /// Every job of the run in submission order, a collector submits one per round
+    pub job_ids: Vec<JobId>,
+    /// Symbols with at least one window downloaded to its end, see `progress` for the other window states
+    pub symbols_processed: usize,
+    /// Rows stored per symbol, over the whole run
+    pub rows_inserted: HashMap<Arc<Symbol>, usize>,
+    /// Rows fetched but lost on a failed insert
+    pub rows_not_inserted: usize,
+    /// Windows that were given up, with the last error
+    pub failures: Vec<(CandlesticksWindow, String)>,
+    pub duration: Duration,
+    /// Klines pages fetched by the jobs
+    pub requests: usize,
+    /// Latest `x-mbx-used-weight-1m` reported by the exchange
+    pub used_weight_1m: Option<u32>,
+    /// Final state of the windows of every job
+    pub progress: DownloadProgress,
+}
+
+impl DownloadReport {
+    /// `jobs` are the final progress of the jobs of the run, in submission order
+    pub fn new(jobs: Vec<DownloadProgress>, rows_inserted: HashMap<Arc<Symbol>, usize>, rows_not_inserted: usize, duration: Duration) -> Self {
+        let job_ids = jobs.iter().filter_map(|job| job.job_id).collect();
+        let progress = Self::merge(jobs);
+        let symbols_processed = progress
+            .windows
+            .iter()
+            .filter(|window| window.state == WindowState::Done)
+            .map(|window| window.window.symbol.clone())
+            .collect::<HashSet<_>>()
+            .len();
+
+        Self {
+            job_id: progress.job_id,
+            job_ids,
+            symbols_processed,
+            failures: progress.failures().into_iter().map(|(window, err)| (window.clone(), err.to_string())).collect(),
+            requests: progress.requests,
+            used_weight_1m: progress.used_weight_1m,
+            rows_inserted,
+            rows_not_inserted,
+            duration,
+            progress,
+        }
+    }
+
+    /// Windows of all the jobs, requests are summed and the latest job gives the id, weight and concurrency
+    fn merge(jobs: Vec<DownloadProgress>) -> DownloadProgress {
+        jobs.into_iter().fold(DownloadProgress::default(), |mut merged, job| {
+            merged.job_id = job.job_id.or(merged.job_id);
+            merged.started_at = merged.started_at.into_iter().chain(job.started_at).min();
+            merged.requests += job.requests;
+            merged.used_weight_1m = job.used_weight_1m.or(merged.used_weight_1m);
+            merged.concurrency = job.concurrency;
+            merged.windows.extend(job.windows);
+            merged
+        })
+    }
+
+    pub fn total_rows_inserted(&self) -> usize {
+        self.rows_inserted.values().sum()
+    }
+
+    /// Nothing failed, was lost or cancelled, a rerun would have nothing to catch up on
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty() && self.rows_not_inserted == 0 && self.progress.count(&WindowState::Cancelled) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::timeframe::Timeframe::{OneHour, ThreeMinutes};
+    use time::OffsetDateTime;
+
+    #[test]
+    fn test_download_report() {
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let sol_usdt = Symbol::from_pool(Binance, "SOL".to_string(), "USDT".to_string());
+        let start = OffsetDateTime::UNIX_EPOCH;
+        let windows = vec![
+            CandlesticksWindow::new(btc_usdt.clone(), ThreeMinutes, start, None),
+            CandlesticksWindow::new(btc_usdt.clone(), OneHour, start, None),
+            CandlesticksWindow::new(eth_usdt.clone(), ThreeMinutes, start, None),
+        ];
+        let mut first_job = DownloadProgress::new(1, &windows);
+        first_job.requests = 7;
+        first_job.set_state(0, WindowState::Done);
+        first_job.set_state(1, WindowState::Done);
+        first_job.set_state(2, WindowState::Failed("timeout".to_string()));
+        let windows = vec![
+            CandlesticksWindow::new(eth_usdt, OneHour, start, None),
+            CandlesticksWindow::new(sol_usdt, OneHour, start, None),
+        ];
+        let mut second_job = DownloadProgress::new(2, &windows);
+        second_job.requests = 3;
+        second_job.set_state(0, WindowState::Cancelled);
+        second_job.set_state(1, WindowState::Skipped);
+
+        let report = DownloadReport::new(vec![first_job, second_job], HashMap::from([(btc_usdt, 42)]), 0, Duration::from_secs(3));
+
+        assert_eq!(Some(2), report.job_id);
+        assert_eq!(vec![1, 2], report.job_ids);
+        // only BTC has a window done, the failed, cancelled and skipped ones are counted by state
+        assert_eq!(1, report.symbols_processed);
+        assert_eq!(1, report.progress.count(&WindowState::Cancelled));
+        assert_eq!(1, report.progress.count(&WindowState::Skipped));
+        assert_eq!(42, report.total_rows_inserted());
+        assert_eq!(10, report.requests);
+        assert_eq!("ETH", report.failures[0].0.symbol.base_asset);
+        assert!(!report.is_complete());
+    }
+}
@@ -1,8 +1,7 @@
 use crate::application::history_reproducer::candlesticks_reproducer_actor::CandlesticksReproducerActor;
 use crate::application::history_reproducer::candlesticks_reproducer_actor::commands::ProduceCandlesticks;
 use crate::config::AppConfig;
-use crate::integrations::clickhouse::candlesticks_repository::CandlesticksRepository;
-use crate::integrations::clickhouse_client;
+use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksFilter, CandlesticksReadService, CandlesticksRepository};
 use actix::{Actor, Addr};
 use candy_ass_core::domain::candlestick::Candlestick;
 use candy_ass_core::domain::timeframe::Timeframe;
@@ -12,39 +11,64 @@ use tokio_stream::wrappers::ReceiverStream;
 
 pub mod candlesticks_reproducer_actor;
 
+/// Wiring of an [`Application`], the store can be replaced, e.g. by a mock or another database
+pub struct ApplicationBuilder {
+    prefetch_buffer: usize,
+    read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+}
+
+impl ApplicationBuilder {
+    /// `read_service` is the source of the replayed candlesticks
+    pub fn new(prefetch_buffer: usize, read_service: Arc<dyn CandlesticksReadService + Send + Sync>) -> Self {
+        Self { prefetch_buffer, read_service }
+    }
+
+    /// Slices read ahead of the consumer
+    pub fn with_prefetch_buffer(mut self, prefetch_buffer: usize) -> Self {
+        self.prefetch_buffer = prefetch_buffer;
+        self
+    }
+
+    /// Starts the actor, it has to run within an actix system
+    pub fn build(self) -> Application {
+        let candlesticks_reproducer_actor = CandlesticksReproducerActor::new(self.prefetch_buffer, self.read_service).start();
+
+        Application { candlesticks_reproducer_actor }
+    }
+}
+
 pub struct Application {
-    _candlesticks_repository: Arc<CandlesticksRepository>,
     candlesticks_reproducer_actor: Addr<CandlesticksReproducerActor>,
 }
 
 impl Application {
     pub fn new(prefetch_buffer: usize, app_config: AppConfig) -> Self {
-        // infrastructure
-        let clickhouse = clickhouse_client(app_config.clickhouse);
-        let candlesticks_repository = Arc::new(CandlesticksRepository::new(clickhouse));
-
-        // actors
-        let candlesticks_reproducer_actor = CandlesticksReproducerActor::new(prefetch_buffer, candlesticks_repository.clone()).start();
+        Self::clickhouse_builder(prefetch_buffer, app_config).build()
+    }
 
-        Application {
-            _candlesticks_repository: candlesticks_repository.clone(),
-            candlesticks_reproducer_actor,
-        }
+    /// Replays the candlesticks of the configured clickhouse
+    pub fn clickhouse_builder(prefetch_buffer: usize, app_config: AppConfig) -> ApplicationBuilder {
+        let candlesticks_repository = Arc::new(CandlesticksRepository::from_config(app_config.clickhouse));
+        ApplicationBuilder::new(prefetch_buffer, candlesticks_repository)
     }
 
+    /// Replays the candlesticks of the `filter` symbols, the other ones are not read from the store
     pub async fn start_pipeline(
         self,
         timeframes: Vec<Timeframe>,
         start_date: OffsetDateTime,
         end_date: OffsetDateTime,
+        filter: CandlesticksFilter,
     ) -> ReceiverStream<(OffsetDateTime, Vec<Candlestick>)> {
         let command = ProduceCandlesticks {
             timeframes,
             start_date,
             end_date,
             step: Duration::days(1),
+            include_unclosed: false,
+            filter,
         };
-        let candlesticks_reproducer_actor = self.candlesticks_reproducer_actor.send(command).await.unwrap().unwrap();
-        ReceiverStream::new(candlesticks_reproducer_actor)
+        let job = self.candlesticks_reproducer_actor.send(command).await.unwrap().unwrap();
+        ReceiverStream::new(job.receiver)
     }
 }
@@ -1,21 +1,32 @@
 pub mod commands;
 pub mod errors;
+pub mod progress;
+pub mod queries;
 
+use crate::application::history_reproducer::candlesticks_reproducer_actor::progress::ReplayProgress;
+use crate::application::job_queue::JobQueue;
+use crate::application::streams::ReplaySlice;
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
 use crate::integrations::clickhouse::candlesticks_repository::CandlesticksReadService;
 use actix::{Actor, Context};
+use futures_util::stream::BoxStream;
 use std::sync::Arc;
+use time::Duration;
+use tokio::sync::{mpsc, watch};
 use tracing::info;
 
-#[derive(Debug, Clone)]
-enum Status {
-    Ready,
-    Busy,
+/// Queued or running replay, `replay` and `sender` are handed over to the job once it starts
+struct ReplayJob {
+    step: Duration,
+    replay: Option<BoxStream<'static, Result<ReplaySlice, ClickhouseRepositoryError>>>,
+    sender: Option<mpsc::Sender<ReplaySlice>>,
+    progress_sender: watch::Sender<ReplayProgress>,
 }
 
 pub struct CandlesticksReproducerActor {
     prefetch_buffer: usize,
     candlesticks_read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
-    status: Status,
+    jobs: JobQueue<ReplayJob>,
 }
 
 impl CandlesticksReproducerActor {
@@ -23,9 +34,15 @@ impl CandlesticksReproducerActor {
         CandlesticksReproducerActor {
             prefetch_buffer,
             candlesticks_read_service,
-            status: Status::Ready,
+            jobs: JobQueue::new(1),
         }
     }
+
+    /// Jobs above the limit wait in the queue until a running one is finished
+    pub fn with_max_concurrent_jobs(mut self, max_concurrent_jobs: usize) -> Self {
+        self.jobs.set_max_running(max_concurrent_jobs);
+        self
+    }
 }
 
 impl Actor for CandlesticksReproducerActor {
@@ -1,74 +1,109 @@
 use crate::application::history_reproducer::candlesticks_reproducer_actor::errors::ReproduceHistoryError;
-use crate::application::history_reproducer::candlesticks_reproducer_actor::{CandlesticksReproducerActor, Status};
-use actix::{AsyncContext, Handler, Message, MessageResult, WrapFuture};
+use crate::application::history_reproducer::candlesticks_reproducer_actor::progress::ReplayProgress;
+use crate::application::history_reproducer::candlesticks_reproducer_actor::{CandlesticksReproducerActor, ReplayJob};
+use crate::application::job_queue::JobReceiver;
+use crate::application::streams::{ReplaySlice, replay_candlesticks};
+use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksFilter, JobId};
+use actix::{ActorFutureExt, AsyncContext, Context, Handler, Message, MessageResult, WrapFuture};
 use candy_ass_core::domain::candlestick::Candlestick;
 use candy_ass_core::domain::timeframe::Timeframe;
+use futures_util::StreamExt;
 use std::ops::Add;
 use time::{Duration, OffsetDateTime};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tokio::time::Instant;
 use tracing::{error, info};
 
+/// Queues a replay, it starts as soon as a running job is finished
 #[derive(Message, Clone)]
-#[rtype(result = "Result<mpsc::Receiver<(OffsetDateTime, Vec<Candlestick>)>, ReproduceHistoryError>")]
+#[rtype(result = "Result<JobReceiver<(OffsetDateTime, Vec<Candlestick>)>, ReproduceHistoryError>")]
 pub struct ProduceCandlesticks {
     pub timeframes: Vec<Timeframe>,
     pub start_date: OffsetDateTime,
     pub end_date: OffsetDateTime,
     pub step: Duration,
+    /// Replays the still-open candlestick of the latest period as well, its prices and volume are not final
+    pub include_unclosed: bool,
+    /// Only these symbols are read from the store
+    pub filter: CandlesticksFilter,
 }
 
 impl Handler<ProduceCandlesticks> for CandlesticksReproducerActor {
     type Result = MessageResult<ProduceCandlesticks>;
 
     fn handle(&mut self, msg: ProduceCandlesticks, ctx: &mut Self::Context) -> Self::Result {
-        let timeframe = msg.timeframes.clone();
-        let start_date = msg.start_date;
-        let end_date = msg.end_date;
+        let replay = match replay_candlesticks(self.candlesticks_read_service.clone(), msg.clone()) {
+            Ok(replay) => replay,
+            Err(err) => return MessageResult(Err(err)),
+        };
 
-        match &self.status {
-            Status::Ready => {
-                let (sender, receiver) = mpsc::channel::<(OffsetDateTime, Vec<Candlestick>)>(self.prefetch_buffer);
-                let candlestick_repository = self.candlesticks_read_service.clone();
+        let job_id = self.jobs.next_job_id();
+        let (sender, receiver) = mpsc::channel::<ReplaySlice>(self.prefetch_buffer);
+        let progress = ReplayProgress {
+            timeframes: msg.timeframes,
+            start_date: msg.start_date,
+            end_date: msg.end_date,
+            replayed_until: None,
+        };
+        let job = ReplayJob {
+            step: msg.step,
+            replay: Some(replay),
+            sender: Some(sender),
+            progress_sender: watch::Sender::new(progress),
+        };
+        self.jobs.push(job_id, job);
+        info!("[CandlesticksReproducerActor] queued job {}", job_id);
 
-                ctx.spawn(
-                    async move {
-                        let step = msg.step;
-                        let mut start_date = start_date;
-                        let mut next_date = start_date.add(step);
-                        while next_date < end_date + step {
-                            let start_timer = Instant::now();
-                            let result = candlestick_repository
-                                .fetch_candlesticks_between(timeframe.clone(), start_date, next_date)
-                                .await;
-                            let duration = start_timer.elapsed();
+        self.schedule(ctx);
+        MessageResult(Ok(JobReceiver { job_id, receiver }))
+    }
+}
 
-                            match result {
-                                Ok(candlesticks) if candlesticks.is_empty() => break,
-                                Ok(candlesticks) => {
-                                    let _ = sender.send((start_date, candlesticks)).await;
-                                    info!(
-                                        "[CandlesticksReproducerActor] candlesticks `{}` are produced in {:?}ms (sender capacity is {})",
-                                        start_date.date(),
-                                        duration.as_millis(),
-                                        sender.capacity()
-                                    );
-                                }
-                                Err(err) => {
-                                    error!("[CandlesticksReproducerActor] Error during candlesticks call: {}", err);
+impl CandlesticksReproducerActor {
+    fn schedule(&mut self, ctx: &mut Context<Self>) {
+        while let Some((job_id, job)) = self.jobs.start_next() {
+            let (Some(sender), Some(mut replay)) = (job.sender.take(), job.replay.take()) else {
+                self.jobs.finish(job_id);
+                continue;
+            };
+            let step = job.step;
+            let progress_sender = job.progress_sender.clone();
+
+            ctx.spawn(
+                async move {
+                    let mut start_timer = Instant::now();
+                    while let Some(result) = replay.next().await {
+                        match result {
+                            Ok((start_date, candlesticks)) => {
+                                let duration = start_timer.elapsed();
+                                if sender.send((start_date, candlesticks)).await.is_err() {
+                                    info!("[CandlesticksReproducerActor] receiver of job {} is dropped", job_id);
+                                    break;
                                 }
+                                progress_sender.send_modify(|progress| progress.replayed_until = Some(start_date.add(step)));
+                                info!(
+                                    "[CandlesticksReproducerActor] candlesticks `{}` are produced in {:?}ms (sender capacity is {})",
+                                    start_date.date(),
+                                    duration.as_millis(),
+                                    sender.capacity()
+                                );
+                            }
+                            Err(err) => {
+                                error!("[CandlesticksReproducerActor] Error during candlesticks call: {}", err);
                             }
-                            start_date = next_date;
-                            next_date = next_date.add(msg.step);
                         }
+                        start_timer = Instant::now();
                     }
-                    .into_actor(self),
-                );
-
-                self.status = Status::Busy;
-                MessageResult(Ok(receiver))
-            }
-            _ => MessageResult(Err(ReproduceHistoryError::ActorIsBusy)),
+                }
+                .into_actor(self)
+                .map(move |_, act, ctx| act.finish(job_id, ctx)),
+            );
         }
     }
+
+    fn finish(&mut self, job_id: JobId, ctx: &mut Context<Self>) {
+        info!("[CandlesticksReproducerActor] finished job {}", job_id);
+        self.jobs.finish(job_id);
+        self.schedule(ctx);
+    }
 }
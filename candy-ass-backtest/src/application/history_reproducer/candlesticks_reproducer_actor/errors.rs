@@ -1,4 +1,7 @@
+use time::Duration;
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum ReproduceHistoryError {
-    ActorIsBusy,
+    /// The replay would never move forward
+    InvalidStep(Duration),
 }
@@ -0,0 +1,12 @@
+use candy_ass_core::domain::timeframe::Timeframe;
+use time::OffsetDateTime;
+
+/// Snapshot of a replay job
+#[derive(Debug, Clone)]
+pub struct ReplayProgress {
+    pub timeframes: Vec<Timeframe>,
+    pub start_date: OffsetDateTime,
+    pub end_date: OffsetDateTime,
+    /// End of the latest slice sent downstream
+    pub replayed_until: Option<OffsetDateTime>,
+}
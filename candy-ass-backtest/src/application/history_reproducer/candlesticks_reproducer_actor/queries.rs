@@ -0,0 +1,25 @@
+use crate::application::history_reproducer::candlesticks_reproducer_actor::CandlesticksReproducerActor;
+use crate::application::history_reproducer::candlesticks_reproducer_actor::progress::ReplayProgress;
+use crate::application::job_queue::JobSummary;
+use actix::{Handler, Message, MessageResult};
+
+/// Queued, running and recently finished jobs, oldest first
+#[derive(Message)]
+#[rtype(result = "Vec<JobSummary<ReplayProgress>>")]
+pub struct ListJobs;
+
+impl Handler<ListJobs> for CandlesticksReproducerActor {
+    type Result = MessageResult<ListJobs>;
+    fn handle(&mut self, _msg: ListJobs, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(
+            self.jobs
+                .list()
+                .map(|(job_id, status, job)| JobSummary {
+                    job_id,
+                    status,
+                    details: job.progress_sender.borrow().clone(),
+                })
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,189 @@
+use crate::integrations::clickhouse::candlesticks_repository::JobId;
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+
+/// Finished jobs kept around for `ListJobs`, older ones are forgotten
+const FINISHED_JOBS_HISTORY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Finished,
+}
+
+/// Reply to a command that enqueued a job: its id and the channel its results are streamed into
+#[derive(Debug)]
+pub struct JobReceiver<T> {
+    pub job_id: JobId,
+    pub receiver: mpsc::Receiver<T>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobSummary<T> {
+    pub job_id: JobId,
+    pub status: JobStatus,
+    pub details: T,
+}
+
+/// Jobs of an actor in submission order, at most `max_running` of them run at the same time
+pub struct JobQueue<J> {
+    max_running: usize,
+    jobs: BTreeMap<JobId, (JobStatus, J)>,
+}
+
+impl<J> JobQueue<J> {
+    pub fn new(max_running: usize) -> Self {
+        Self {
+            max_running: max_running.max(1),
+            jobs: BTreeMap::new(),
+        }
+    }
+
+    pub fn set_max_running(&mut self, max_running: usize) {
+        self.max_running = max_running.max(1);
+    }
+
+    pub fn max_running(&self) -> usize {
+        self.max_running
+    }
+
+    pub fn push(&mut self, job_id: JobId, job: J) {
+        self.jobs.insert(job_id, (JobStatus::Queued, job));
+    }
+
+    pub fn get(&self, job_id: JobId) -> Option<&J> {
+        self.jobs.get(&job_id).map(|(_, job)| job)
+    }
+
+    pub fn get_mut(&mut self, job_id: JobId) -> Option<&mut J> {
+        self.jobs.get_mut(&job_id).map(|(_, job)| job)
+    }
+
+    pub fn status(&self, job_id: JobId) -> Option<JobStatus> {
+        self.jobs.get(&job_id).map(|(status, _)| *status)
+    }
+
+    /// Unix time in microseconds, kept increasing for jobs submitted within the same microsecond
+    pub fn next_job_id(&self) -> JobId {
+        let now = (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000) as JobId;
+        self.jobs.keys().next_back().map_or(now, |last_job_id| now.max(last_job_id + 1))
+    }
+
+    /// Marks the oldest queued job as running, if there is a free slot
+    pub fn start_next(&mut self) -> Option<(JobId, &mut J)> {
+        if self.count(JobStatus::Running) >= self.max_running {
+            return None;
+        }
+        self.jobs
+            .iter_mut()
+            .find(|(_, (status, _))| *status == JobStatus::Queued)
+            .map(|(job_id, (status, job))| {
+                *status = JobStatus::Running;
+                (*job_id, job)
+            })
+    }
+
+    /// Puts a running job back in front of the queue, it keeps its id and therefore its place
+    pub fn requeue(&mut self, job_id: JobId) {
+        if let Some((status, _)) = self.jobs.get_mut(&job_id).filter(|(status, _)| *status == JobStatus::Running) {
+            *status = JobStatus::Queued;
+        }
+    }
+
+    pub fn finish(&mut self, job_id: JobId) {
+        if let Some((status, _)) = self.jobs.get_mut(&job_id) {
+            *status = JobStatus::Finished;
+        }
+
+        let finished = self.ids(JobStatus::Finished);
+        for job_id in finished.iter().take(finished.len().saturating_sub(FINISHED_JOBS_HISTORY)) {
+            self.jobs.remove(job_id);
+        }
+    }
+
+    /// Queued and running jobs
+    pub fn active(&self) -> Vec<JobId> {
+        self.jobs
+            .iter()
+            .filter(|(_, (status, _))| *status != JobStatus::Finished)
+            .map(|(job_id, _)| *job_id)
+            .collect()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.count(JobStatus::Running) == 0
+    }
+
+    pub fn list(&self) -> impl Iterator<Item = (JobId, JobStatus, &J)> {
+        self.jobs.iter().map(|(job_id, (status, job))| (*job_id, *status, job))
+    }
+
+    fn ids(&self, status: JobStatus) -> Vec<JobId> {
+        self.list()
+            .filter(|(_, job_status, _)| *job_status == status)
+            .map(|(job_id, _, _)| job_id)
+            .collect()
+    }
+
+    fn count(&self, status: JobStatus) -> usize {
+        self.jobs.values().filter(|(job_status, _)| *job_status == status).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jobs_start_in_submission_order() {
+        let mut queue = JobQueue::new(1);
+        queue.push(2, "second");
+        queue.push(1, "first");
+
+        assert_eq!(Some((1, &mut "first")), queue.start_next());
+        assert_eq!(None, queue.start_next());
+        assert_eq!(Some(JobStatus::Queued), queue.status(2));
+
+        queue.finish(1);
+        assert_eq!(Some((2, &mut "second")), queue.start_next());
+        assert_eq!(vec![2], queue.active());
+        assert!(!queue.is_idle());
+    }
+
+    #[test]
+    fn test_requeued_job_starts_first() {
+        let mut queue = JobQueue::new(1);
+        queue.push(1, "first");
+        queue.push(2, "second");
+        queue.start_next();
+
+        queue.requeue(1);
+        assert!(queue.is_idle());
+        assert_eq!(Some((1, &mut "first")), queue.start_next());
+    }
+
+    #[test]
+    fn test_job_ids_are_increasing() {
+        let mut queue = JobQueue::new(1);
+        let first = queue.next_job_id();
+        queue.push(first, ());
+
+        assert!(queue.next_job_id() > first);
+    }
+
+    #[test]
+    fn test_finished_jobs_history_is_bounded() {
+        let mut queue = JobQueue::new(1);
+        for job_id in 0..(FINISHED_JOBS_HISTORY as JobId + 4) {
+            queue.push(job_id, ());
+            queue.start_next();
+            queue.finish(job_id);
+        }
+
+        assert_eq!(FINISHED_JOBS_HISTORY, queue.list().count());
+        assert_eq!(None, queue.status(0));
+        assert!(queue.is_idle());
+    }
+}
@@ -0,0 +1,117 @@
+//! Actor-free download and replay, they run on any tokio runtime without an actix system.
+//! Both are built on the same client and repository traits as the applications.
+
+use crate::application::history_downloader::candlesticks_downloader_actor::commands::download_candlesticks::{Fetcher, stream_shards};
+use crate::application::history_downloader::candlesticks_downloader_actor::concurrency::{ConcurrencyController, RequestPacer};
+use crate::application::history_downloader::candlesticks_downloader_actor::{DEFAULT_SHARD_PAGES, PartialCandlePolicy};
+use crate::application::history_reproducer::candlesticks_reproducer_actor::commands::ProduceCandlesticks;
+use crate::application::history_reproducer::candlesticks_reproducer_actor::errors::ReproduceHistoryError;
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use crate::integrations::clickhouse::candlesticks_repository::CandlesticksReadService;
+use candy_ass_core::domain::candlestick::Candlestick;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::integrations::http::HttpResponseError;
+use candy_ass_core::integrations::http::binance::BINANCE_RATE_LIMIT;
+use candy_ass_core::integrations::http::binance::spot_http_client::KlinesApi;
+use candy_ass_core::utils::RetryPolicy;
+use futures_util::stream::BoxStream;
+use futures_util::{StreamExt, TryStreamExt, future, stream};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use time::{Duration, OffsetDateTime};
+
+/// Slice of a replay: its start and the candlesticks opened within it
+pub type ReplaySlice = (OffsetDateTime, Vec<Candlestick>);
+
+/// Parameters of [`download_candlesticks`], the defaults match the downloader actor
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Page requests in flight, it doesn't adapt to the exchange like the actor does
+    pub concurrency: usize,
+    /// Milliseconds between two requests
+    pub rate_limit: usize,
+    pub retry_policy: RetryPolicy,
+    pub partial_candle_policy: PartialCandlePolicy,
+    /// Requests per date shard, the shards of a window are fetched in parallel
+    pub shard_pages: usize,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            rate_limit: BINANCE_RATE_LIMIT,
+            retry_policy: RetryPolicy::default(),
+            partial_candle_policy: PartialCandlePolicy::default(),
+            shard_pages: DEFAULT_SHARD_PAGES,
+        }
+    }
+}
+
+/// Downloads the windows `concurrency` pages at a time, the pages of every window come in date order.
+/// A window that still fails after the retries yields its error and ends, the other windows carry on.
+/// Nothing is stored, dropping the stream stops the download.
+pub fn download_candlesticks(
+    klines_api: Arc<dyn KlinesApi + Send + Sync>,
+    windows: Vec<CandlesticksWindow>,
+    options: DownloadOptions,
+) -> BoxStream<'static, Result<Vec<Candlestick>, HttpResponseError>> {
+    let concurrency = options.concurrency.max(1);
+    let fetcher = Fetcher {
+        binance_client: klines_api,
+        retry_policy: options.retry_policy,
+        request_pacer: RequestPacer::new(std::time::Duration::from_millis(options.rate_limit as u64)),
+        concurrency_controller: ConcurrencyController::new(concurrency, concurrency),
+        cancelled: Arc::new(AtomicBool::new(false)),
+    };
+    let parallel_shards = (concurrency / windows.len().max(1)).max(1);
+    let shard_pages = options.shard_pages.max(1);
+    let partial_candle_policy = options.partial_candle_policy;
+
+    stream::iter(windows)
+        .map(move |window| stream_shards(fetcher.clone(), window, shard_pages, parallel_shards).boxed())
+        .flatten_unordered(concurrency)
+        .map_ok(move |(candlesticks, _)| partial_candle_policy.apply(candlesticks))
+        .try_filter(|candlesticks| future::ready(!candlesticks.is_empty()))
+        .boxed()
+}
+
+/// Replays the stored candlesticks of the filtered symbols from `start_date` to `end_date` in `step` long slices.
+/// Empty slices are skipped, a failed fetch is yielded and the replay goes on with the next slice until `end_date`.
+pub fn replay_candlesticks(
+    read_service: Arc<dyn CandlesticksReadService + Send + Sync>,
+    request: ProduceCandlesticks,
+) -> Result<BoxStream<'static, Result<ReplaySlice, ClickhouseRepositoryError>>, ReproduceHistoryError> {
+    let ProduceCandlesticks {
+        timeframes,
+        start_date,
+        end_date,
+        step,
+        include_unclosed,
+        filter,
+    } = request;
+    if step <= Duration::ZERO {
+        return Err(ReproduceHistoryError::InvalidStep(step));
+    }
+
+    Ok(stream::unfold(start_date, move |mut slice_start| {
+        let read_service = read_service.clone();
+        let timeframes = timeframes.clone();
+        let filter = filter.clone();
+        async move {
+            while slice_start < end_date {
+                let slice_end = slice_start + step;
+                match read_service
+                    .fetch_candlesticks_between(timeframes.clone(), slice_start, slice_end, include_unclosed, filter.clone())
+                    .await
+                {
+                    Ok(candlesticks) if candlesticks.is_empty() => slice_start = slice_end,
+                    Ok(candlesticks) => return Some((Ok((slice_start, candlesticks)), slice_end)),
+                    Err(err) => return Some((Err(err), slice_end)),
+                }
+            }
+            None
+        }
+    })
+    .boxed())
+}
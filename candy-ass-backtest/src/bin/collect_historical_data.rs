@@ -0,0 +1,73 @@
+use candy_ass_backtest::application::history_downloader::{Application, CollectorSchedule, TimeframeSchedule};
+use candy_ass_backtest::config::AppConfig;
+use candy_ass_core::domain::symbol::SymbolFilterFn;
+use candy_ass_core::domain::timeframe::Timeframe::{OneHour, ThreeMinutes};
+use futures_util::{FutureExt, StreamExt};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::level_filters::LevelFilter;
+use tracing::{error, info, warn};
+
+/// Keeps the database current until Ctrl-C, new listings are picked up with the hourly symbols refresh
+/// Exits with 1 when the collector could not start
+#[actix::main]
+async fn main() -> ExitCode {
+    AppConfig::default_setup(LevelFilter::INFO);
+    let config = AppConfig::from_env().expect("Failed to load application config");
+    let application = Application::new_collector(50, 14, config, Duration::from_secs(60 * 60));
+
+    let start_date = OffsetDateTime::parse("2023-01-01T00:00:00Z", &Rfc3339).unwrap();
+    let filter: SymbolFilterFn = Arc::new(|symbol| symbol.quote_asset == "USDT");
+    let schedule = CollectorSchedule {
+        timeframes: vec![
+            TimeframeSchedule {
+                timeframe: ThreeMinutes,
+                start_date,
+                interval: Duration::from_secs(60),
+            },
+            TimeframeSchedule {
+                timeframe: OneHour,
+                start_date,
+                interval: Duration::from_secs(5 * 60),
+            },
+        ],
+        filter,
+        optimization_interval: Some(Duration::from_secs(24 * 60 * 60)),
+    };
+
+    // the collector runs unattended, restarts of its actors are worth a trace in its own log
+    if let Ok(lifecycle_events) = application.lifecycle_events().await {
+        actix::spawn(lifecycle_events.for_each(|event| async move { warn!("Collector actor lifecycle: {:?}", event) }));
+    }
+
+    let mut collector = application.start_collector(schedule).boxed_local();
+    let report = tokio::select! {
+        report = &mut collector => report,
+        _ = tokio::signal::ctrl_c() => {
+            info!("Ctrl-C received, stopping the collector and flushing buffered candlesticks");
+            application.cancel().await;
+            collector.await
+        }
+    };
+    application.shutdown().await;
+    match report {
+        Ok(report) => {
+            info!(
+                "Collector stopped after {:?}, {} candlesticks of {} symbols were stored by {} jobs, the last job was {:?}",
+                report.duration,
+                report.total_rows_inserted(),
+                report.symbols_processed,
+                report.job_ids.len(),
+                report.job_id
+            );
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            error!("Collector failed: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,6 +1,9 @@
-use candy_ass_backtest::application::history_downloader::Application;
+use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::progress::WindowState;
+use candy_ass_backtest::application::history_downloader::{Application, DownloadMode, DownloadRequest};
 use candy_ass_core::domain::timeframe::Timeframe::ThreeMinutes;
+use futures_util::FutureExt;
 use std::io;
+use std::process::ExitCode;
 
 use candy_ass_backtest::config::AppConfig;
 use candy_ass_core::domain::symbol::SymbolFilterFn;
@@ -8,18 +11,102 @@ use std::sync::Arc;
 use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tracing::info;
 use tracing::level_filters::LevelFilter;
+use tracing::{error, info, warn};
 
+/// Exits with 1 when nothing could be downloaded and with 2 when some windows or inserts failed or the run was cancelled
 #[actix::main]
-async fn main() {
+async fn main() -> ExitCode {
     AppConfig::default_setup(LevelFilter::INFO);
     let config = AppConfig::from_env().expect("Failed to load application config");
     let application = Application::new(50, 14, config);
 
     let start_date = OffsetDateTime::parse("2023-01-01T00:00:00Z", &Rfc3339).unwrap();
     let filter: SymbolFilterFn = Arc::new(|symbol| symbol.quote_asset == "USDT");
-    application.start_pipeline(ThreeMinutes, start_date, filter).await;
+    let request = DownloadRequest {
+        timeframes: vec![(ThreeMinutes, start_date)],
+        end_date: None,
+        filter,
+        mode: DownloadMode::Resume,
+    };
+
+    // `--plan` only estimates the download, a job id of a previous run can be passed to continue it where it stopped
+    let mut pipeline = match std::env::args().nth(1) {
+        Some(arg) if arg == "--plan" => {
+            return match application.plan_pipeline(request).await {
+                Ok(plan) => {
+                    for symbol in plan.symbols() {
+                        info!(
+                            "{}: {} candlesticks in {} requests",
+                            symbol.symbol.short_name(),
+                            symbol.expected_candles,
+                            symbol.requests
+                        );
+                    }
+                    for symbol in plan.unknown_listing_symbols() {
+                        info!("{}: listing date unknown, it is looked up when the download starts", symbol.short_name());
+                    }
+                    info!(
+                        "Plan: {} symbols, {} candlesticks, {} requests ({} listing lookups), weight {}, ETA {:?}, {} windows not listed, \
+                         {} windows with an unknown listing date",
+                        plan.symbols().len(),
+                        plan.expected_candles(),
+                        plan.requests,
+                        plan.listing_lookups,
+                        plan.weight,
+                        plan.eta,
+                        plan.skipped.len(),
+                        plan.unknown_listing.len(),
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    error!("Planning failed: {}", err);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Some(job_id) => {
+            let job_id = job_id.parse().expect("Job id must be a number");
+            application.resume_pipeline(job_id).boxed_local()
+        }
+        None => application.start_pipeline(request).boxed_local(),
+    };
+    let report = tokio::select! {
+        report = &mut pipeline => report,
+        _ = tokio::signal::ctrl_c() => {
+            info!("Ctrl-C received, cancelling the download and flushing buffered candlesticks");
+            application.cancel().await;
+            pipeline.await
+        }
+    };
+    application.shutdown().await;
+    let report = match report {
+        Ok(report) => report,
+        Err(err) => {
+            error!("Download failed: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    info!(
+        "Job {:?} stored {} candlesticks of {} symbols in {:?} with {} requests (used weight {:?}): {} windows done, {} not listed, {} cancelled, {} failed",
+        report.job_id,
+        report.total_rows_inserted(),
+        report.symbols_processed,
+        report.duration,
+        report.requests,
+        report.used_weight_1m,
+        report.progress.count(&WindowState::Done),
+        report.progress.count(&WindowState::Skipped),
+        report.progress.count(&WindowState::Cancelled),
+        report.failures.len(),
+    );
+    for (window, err) in &report.failures {
+        warn!("Failed to download {}: {}", window, err);
+    }
+    if report.rows_not_inserted > 0 {
+        warn!("{} candlesticks were downloaded but not stored", report.rows_not_inserted);
+    }
 
     info!("Import completed. Would you like to run clickhouse optimization ? [y/n]");
     let mut input = String::new();
@@ -34,4 +121,9 @@ async fn main() {
 
     println!("Press Enter, to exit...");
     let _ = BufReader::new(tokio::io::stdin()).read_line(&mut String::new()).await;
+
+    match report.is_complete() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::from(2),
+    }
 }
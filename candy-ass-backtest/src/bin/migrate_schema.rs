@@ -0,0 +1,71 @@
+use candy_ass_backtest::config::AppConfig;
+use candy_ass_backtest::integrations::clickhouse::candlesticks_repository::{CandlesticksRepository, SchemaMigrationsService};
+use candy_ass_backtest::integrations::clickhouse::migrations::MigrationState;
+use std::process::ExitCode;
+use tracing::level_filters::LevelFilter;
+use tracing::{error, info, warn};
+
+/// `migrate_schema` applies the pending migrations, `migrate_schema status` only lists them.
+/// `migrate_schema copy-layout` copies the candlesticks of the other layout into the configured one, run it after switching the layout.
+/// Exits with 1 when the schema can't be read or migrated.
+#[tokio::main]
+async fn main() -> ExitCode {
+    AppConfig::default_setup(LevelFilter::INFO);
+    let config = AppConfig::from_env().expect("Failed to load application config");
+    let layout = config.clickhouse.layout;
+    let repository = CandlesticksRepository::from_config(config.clickhouse);
+
+    match std::env::args().nth(1) {
+        Some(arg) if arg == "status" => match repository.migration_status().await {
+            Ok(statuses) => {
+                for status in statuses {
+                    match status.state {
+                        MigrationState::Applied(applied_at) => info!("{:>4} {}: applied at {}", status.version, status.name, applied_at),
+                        MigrationState::Pending => info!("{:>4} {}: pending", status.version, status.name),
+                        MigrationState::ChecksumMismatch { recorded, expected } => warn!(
+                            "{:>4} {}: edited after it had been applied, checksum {:x} instead of {:x}",
+                            status.version, status.name, expected, recorded
+                        ),
+                        MigrationState::Unknown => warn!("{:>4} {}: unknown, applied by a newer version", status.version, status.name),
+                    }
+                }
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                error!("Failed to read the schema migrations: {}", err);
+                ExitCode::FAILURE
+            }
+        },
+        Some(arg) if arg == "copy-layout" => match repository.migrate().await {
+            Ok(_) => match repository.copy_candlesticks(layout.other()).await {
+                Ok(_) => ExitCode::SUCCESS,
+                Err(err) => {
+                    error!("Failed to copy the candlesticks of the {:?} layout: {}", layout.other(), err);
+                    ExitCode::FAILURE
+                }
+            },
+            Err(err) => {
+                error!("Migration failed: {}", err);
+                ExitCode::FAILURE
+            }
+        },
+        Some(arg) => {
+            error!("Unknown command {}, expected no argument, `status` or `copy-layout`", arg);
+            ExitCode::FAILURE
+        }
+        None => match repository.migrate().await {
+            Ok(applied) if applied.is_empty() => {
+                info!("Schema is up to date");
+                ExitCode::SUCCESS
+            }
+            Ok(applied) => {
+                info!("Applied migrations {:?}", applied);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                error!("Migration failed: {}", err);
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
@@ -2,6 +2,7 @@ use candy_ass_backtest::application::history_reproducer::Application;
 use futures_util::StreamExt;
 
 use candy_ass_backtest::config::AppConfig;
+use candy_ass_backtest::integrations::clickhouse::candlesticks_repository::CandlesticksFilter;
 
 use candy_ass_core::domain::timeframe::Timeframe::ThreeMinutes;
 use time::OffsetDateTime;
@@ -20,9 +21,11 @@ async fn main() {
     let timeframes = vec![ThreeMinutes];
     let start_date = OffsetDateTime::parse("2024-01-01T00:00:00Z", &Rfc3339).unwrap();
     let end_date = OffsetDateTime::parse("2024-02-01T00:00:00Z", &Rfc3339).unwrap();
+    // only the filtered symbols are read from clickhouse, e.g. `.with_base_assets(vec!["BTC".into(), "ETH".into()])`
+    let filter = CandlesticksFilter::default().with_quote_assets(vec!["USDT".to_string()]);
 
     let timer = Instant::now();
-    let pipeline = application.start_pipeline(timeframes, start_date, end_date).await;
+    let pipeline = application.start_pipeline(timeframes, start_date, end_date, filter).await;
     let processed = pipeline
         .flat_map(|(_date_time, candlesticks)| futures::stream::iter(candlesticks))
         //.then(_ {}) add your pipeline right here
@@ -1,6 +1,6 @@
 use config::{Config, ConfigError, File};
 use serde::Deserialize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::level_filters::LevelFilter;
 
 #[derive(Debug, Deserialize)]
@@ -9,11 +9,124 @@ pub struct ClickhouseConfig {
     pub port: u16,
     pub username: String,
     pub password: String,
+    /// `candy_ass` and its default tables unless configured
+    #[serde(default)]
+    pub tables: TableNames,
+    #[serde(default)]
+    pub layout: StorageLayout,
+}
+
+/// Table the candlesticks are stored in, it decides which reads the primary key serves.
+/// Both tables are created by the migrations, switching the layout doesn't move stored candlesticks,
+/// `migrate_schema copy-layout` copies them from the table of the other layout.
+/// It is a setting of the whole dataset, reads can't pick a path per query: only one table is written,
+/// and in `SymbolFirst` clickhouse already picks the symbol order or the time projection for each query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageLayout {
+    /// `candlesticks` sorted by time first, fit for time-slice replays.
+    /// Single-symbol reads scan the whole time range, every granule holds all the symbols of its time.
+    #[default]
+    TimeFirst,
+    /// `candlesticks_by_symbol` in monthly partitions sorted by symbol first, fit for single-symbol studies.
+    /// Time-slice replays are served by a projection sorted by time, the optimization only rewrites unmerged months.
+    SymbolFirst,
+}
+
+impl StorageLayout {
+    pub fn other(self) -> Self {
+        match self {
+            StorageLayout::TimeFirst => StorageLayout::SymbolFirst,
+            StorageLayout::SymbolFirst => StorageLayout::TimeFirst,
+        }
+    }
+}
+
+/// Database and tables the repository works with, so several datasets can share one clickhouse.
+/// Names are used as identifiers, they are quoted but not escaped.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TableNames {
+    pub database: String,
+    pub candlesticks: String,
+    pub candlesticks_by_symbol: String,
+    pub download_jobs: String,
+    pub download_checkpoints: String,
+    pub listing_dates: String,
+    pub schema_migrations: String,
+}
+
+impl Default for TableNames {
+    fn default() -> Self {
+        Self {
+            database: "candy_ass".to_string(),
+            candlesticks: "candlesticks".to_string(),
+            candlesticks_by_symbol: "candlesticks_by_symbol".to_string(),
+            download_jobs: "download_jobs".to_string(),
+            download_checkpoints: "download_checkpoints".to_string(),
+            listing_dates: "listing_dates".to_string(),
+            schema_migrations: "schema_migrations".to_string(),
+        }
+    }
+}
+
+impl TableNames {
+    pub fn database_name(&self) -> String {
+        format!("`{}`", self.database)
+    }
+
+    pub fn candlesticks_table(&self) -> String {
+        self.qualified(&self.candlesticks)
+    }
+
+    pub fn candlesticks_by_symbol_table(&self) -> String {
+        self.qualified(&self.candlesticks_by_symbol)
+    }
+
+    pub fn download_jobs_table(&self) -> String {
+        self.qualified(&self.download_jobs)
+    }
+
+    pub fn download_checkpoints_table(&self) -> String {
+        self.qualified(&self.download_checkpoints)
+    }
+
+    pub fn listing_dates_table(&self) -> String {
+        self.qualified(&self.listing_dates)
+    }
+
+    pub fn schema_migrations_table(&self) -> String {
+        self.qualified(&self.schema_migrations)
+    }
+
+    /// Replaces the `{database}` and `{<table>}` placeholders of a statement template with the quoted names
+    pub fn render(&self, template: &str) -> String {
+        template
+            .replace("{database}", &self.database_name())
+            .replace("{candlesticks}", &self.candlesticks_table())
+            .replace("{candlesticks_by_symbol}", &self.candlesticks_by_symbol_table())
+            .replace("{download_jobs}", &self.download_jobs_table())
+            .replace("{download_checkpoints}", &self.download_checkpoints_table())
+            .replace("{listing_dates}", &self.listing_dates_table())
+            .replace("{schema_migrations}", &self.schema_migrations_table())
+    }
+
+    fn qualified(&self, table: &str) -> String {
+        format!("`{}`.`{}`", self.database, table)
+    }
+}
+
+/// Local directory the batches clickhouse fails to insert are spooled into, until they are replayed
+#[derive(Debug, Deserialize)]
+pub struct SpoolConfig {
+    pub path: PathBuf,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
     pub clickhouse: ClickhouseConfig,
+    /// Failed inserts are lost without it
+    pub spool: Option<SpoolConfig>,
 }
 
 impl AppConfig {
@@ -36,3 +149,38 @@ impl AppConfig {
         Config::builder().add_source(File::from(path.as_ref())).build()?.try_deserialize()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statements_are_rendered_with_the_configured_names() {
+        // given
+        let tables = TableNames {
+            database: "staging".to_string(),
+            candlesticks: "klines".to_string(),
+            ..TableNames::default()
+        };
+
+        // when
+        let statement = tables.render("INSERT INTO {candlesticks} SELECT * FROM {download_jobs}, {database}.other");
+
+        // then
+        assert_eq!(
+            "INSERT INTO `staging`.`klines` SELECT * FROM `staging`.`download_jobs`, `staging`.other",
+            statement
+        );
+    }
+
+    #[test]
+    fn test_default_table_names() {
+        // when
+        let config = AppConfig::from_file("tests/default.yaml").unwrap();
+
+        // then
+        assert_eq!(TableNames::default(), config.clickhouse.tables);
+        assert_eq!("`candy_ass`.`candlesticks`", config.clickhouse.tables.candlesticks_table());
+        assert_eq!(StorageLayout::TimeFirst, config.clickhouse.layout);
+    }
+}
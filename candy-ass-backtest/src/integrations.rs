@@ -3,6 +3,7 @@ use ::clickhouse::{Client, Compression};
 use std::sync::Arc;
 
 pub mod clickhouse;
+pub mod spool;
 
 pub fn clickhouse_client(config: ClickhouseConfig) -> Arc<Client> {
     let client = Client::default()
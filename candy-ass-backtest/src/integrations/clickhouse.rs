@@ -5,6 +5,7 @@ use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
 
 pub mod candlesticks_repository;
+pub mod migrations;
 pub mod model;
 
 /// Utils
@@ -24,6 +25,15 @@ pub enum ClickhouseRepositoryError {
 
     #[error("Failed to parse a row: {0}")]
     ParsingError(#[from] ParseError),
+
+    #[error("Migration {version} ({name}) was edited after it had been applied")]
+    MigrationChecksumMismatch { version: u32, name: String },
+
+    #[error("Migration {version} ({name}) is unknown, the schema was migrated by a newer version")]
+    UnknownMigration { version: u32, name: String },
+
+    #[error("Failed to access the spool: {0}")]
+    SpoolError(#[from] std::io::Error),
 }
 
 /// tests
@@ -1,36 +1,229 @@
+pub mod checkpoints_service;
+pub mod listing_dates_service;
+pub mod migrations_service;
 pub mod read_service;
 pub mod write_service;
 
+use crate::config::{ClickhouseConfig, StorageLayout, TableNames};
 use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use crate::integrations::clickhouse::migrations::MigrationStatus;
+use crate::integrations::clickhouse_client;
 use candy_ass_core::domain::candlestick::Candlestick;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::domain::exchange_type::ExchangeType;
+use candy_ass_core::domain::symbol::Symbol;
 use candy_ass_core::domain::timeframe::Timeframe;
 use clickhouse::Client;
 use futures_util::future::BoxFuture;
+use std::collections::HashMap;
 use std::sync::Arc;
 use time::OffsetDateTime;
 
+/// `open_time` of the latest stored candlestick per (symbol, timeframe)
+pub type LastOpenTimes = HashMap<(Arc<Symbol>, Timeframe), OffsetDateTime>;
+
+/// Identifier of a download job, recorded along with its checkpoints
+pub type JobId = u64;
+
+/// `open_time` of the latest persisted candlestick per window of a job,
+/// keyed by (symbol, timeframe, window start in the job plan) since a job may hold several windows of a pair
+pub type WindowCheckpoints = HashMap<(Arc<Symbol>, Timeframe, OffsetDateTime), OffsetDateTime>;
+
+/// `open_time` of the first candlestick the exchange lists per (symbol, timeframe)
+pub type KnownListingDates = HashMap<(Arc<Symbol>, Timeframe), OffsetDateTime>;
+
+/// Window along with its start in the job plan, the start keys its checkpoints even once the window is trimmed or resumed
+pub type PlannedWindow = (OffsetDateTime, CandlesticksWindow);
+
+/// Symbols a read is narrowed to, its predicates are pushed down into the `WHERE` clause.
+/// An empty list doesn't restrict, the others must all match, e.g. `BTC` and `ETH` base assets quoted in `USDT`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CandlesticksFilter {
+    pub exchanges: Vec<ExchangeType>,
+    pub base_assets: Vec<String>,
+    pub quote_assets: Vec<String>,
+    /// Exact symbols, for pairs the asset lists can't express
+    pub symbols: Vec<Arc<Symbol>>,
+}
+
+impl CandlesticksFilter {
+    pub fn with_exchanges(mut self, exchanges: Vec<ExchangeType>) -> Self {
+        self.exchanges = exchanges;
+        self
+    }
+
+    pub fn with_base_assets(mut self, base_assets: Vec<String>) -> Self {
+        self.base_assets = base_assets;
+        self
+    }
+
+    pub fn with_quote_assets(mut self, quote_assets: Vec<String>) -> Self {
+        self.quote_assets = quote_assets;
+        self
+    }
+
+    pub fn with_symbols(mut self, symbols: Vec<Arc<Symbol>>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Same predicates as the pushed down ones, for stores that can't filter themselves
+    pub fn matches(&self, symbol: &Symbol) -> bool {
+        (self.exchanges.is_empty() || self.exchanges.contains(&symbol.exchange_type))
+            && (self.base_assets.is_empty() || self.base_assets.contains(&symbol.base_asset))
+            && (self.quote_assets.is_empty() || self.quote_assets.contains(&symbol.quote_asset))
+            && (self.symbols.is_empty() || self.symbols.iter().any(|filtered| **filtered == *symbol))
+    }
+}
+
 pub struct CandlesticksRepository {
     client: Arc<Client>,
+    tables: TableNames,
+    layout: StorageLayout,
 }
 
 impl CandlesticksRepository {
     pub fn new(client: Arc<Client>) -> CandlesticksRepository {
-        CandlesticksRepository { client }
+        CandlesticksRepository {
+            client,
+            tables: TableNames::default(),
+            layout: StorageLayout::default(),
+        }
+    }
+
+    /// Client, tables and layout of the configuration
+    pub fn from_config(config: ClickhouseConfig) -> CandlesticksRepository {
+        let tables = config.tables.clone();
+        let layout = config.layout;
+        Self::new(clickhouse_client(config)).with_tables(tables).with_layout(layout)
+    }
+
+    /// Every statement of the repository goes to these tables, the migrations create them
+    pub fn with_tables(mut self, tables: TableNames) -> Self {
+        self.tables = tables;
+        self
+    }
+
+    /// Candlesticks are stored in and read from the table of the layout
+    pub fn with_layout(mut self, layout: StorageLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    fn candlesticks_table(&self) -> String {
+        self.layout_table(self.layout)
+    }
+
+    fn layout_table(&self, layout: StorageLayout) -> String {
+        match layout {
+            StorageLayout::TimeFirst => self.tables.candlesticks_table(),
+            StorageLayout::SymbolFirst => self.tables.candlesticks_by_symbol_table(),
+        }
     }
 }
 
 /// Services
 pub trait CandlesticksWriteService {
-    fn init(&self) -> BoxFuture<Result<(), ClickhouseRepositoryError>>;
-    fn bulk_insert_candlesticks(&self, chunk: Vec<Vec<Candlestick>>) -> BoxFuture<Result<(), ClickhouseRepositoryError>>;
-    fn run_optimization(&self) -> BoxFuture<Result<(), ClickhouseRepositoryError>>;
+    /// Brings the schema up to date, see [`SchemaMigrationsService::migrate`]
+    fn init(&self) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>>;
+    fn bulk_insert_candlesticks(&self, chunk: Vec<Vec<Candlestick>>) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>>;
+    fn run_optimization(&self) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>>;
 }
 
 pub trait CandlesticksReadService {
+    /// Still-open candlesticks are left out unless `include_unclosed` is set,
+    /// rows stored before the `is_closed` flag existed are judged by their `close_time`.
+    /// Only the symbols of the `filter` are read.
     fn fetch_candlesticks_between(
         &self,
         timeframe: Vec<Timeframe>,
         from: OffsetDateTime,
         to: OffsetDateTime,
-    ) -> BoxFuture<Result<Vec<Candlestick>, ClickhouseRepositoryError>>;
+        include_unclosed: bool,
+        filter: CandlesticksFilter,
+    ) -> BoxFuture<'_, Result<Vec<Candlestick>, ClickhouseRepositoryError>>;
+
+    fn fetch_last_open_times(&self, timeframes: Vec<Timeframe>) -> BoxFuture<'_, Result<LastOpenTimes, ClickhouseRepositoryError>>;
+
+    /// Candlesticks of a single symbol and timeframe, e.g. for a study of its whole history.
+    /// Unlike [`CandlesticksReadService::fetch_candlesticks_between`] it reads by symbol, which the symbol-first layout serves best.
+    fn fetch_symbol_candlesticks(
+        &self,
+        symbol: Arc<Symbol>,
+        timeframe: Timeframe,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        include_unclosed: bool,
+    ) -> BoxFuture<'_, Result<Vec<Candlestick>, ClickhouseRepositoryError>>;
+
+    /// Missing `open_time` ranges of the symbols within `from..to`, judged by the `timeframe` cadence:
+    /// holes between stored candlesticks, the ranges before the first and after the last stored one,
+    /// and the whole range for symbols with nothing stored
+    fn fetch_gaps(
+        &self,
+        symbols: Vec<Arc<Symbol>>,
+        timeframe: Timeframe,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> BoxFuture<'_, Result<Vec<CandlesticksWindow>, ClickhouseRepositoryError>>;
+}
+
+/// Plan and progress of download jobs, so a killed job can be continued
+pub trait DownloadCheckpointsService {
+    /// Records the job parameters as the list of its windows
+    fn create_job(&self, job_id: JobId, windows: Vec<CandlesticksWindow>) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>>;
+
+    /// Records the latest persisted `open_time` per window, must be called only after the candlesticks are stored
+    fn commit_checkpoints(&self, job_id: JobId, checkpoints: WindowCheckpoints) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>>;
+
+    /// Windows of the job that are not persisted yet, each of them starts right after its own checkpoint
+    fn fetch_remaining_windows(&self, job_id: JobId) -> BoxFuture<'_, Result<Vec<PlannedWindow>, ClickhouseRepositoryError>>;
+}
+
+/// Listing dates discovered by previous runs, so a symbol is looked up on the exchange only once
+pub trait ListingDatesService {
+    fn fetch_listing_dates(&self) -> BoxFuture<'_, Result<KnownListingDates, ClickhouseRepositoryError>>;
+
+    /// A listing date never changes, saving it again only replaces the row
+    fn save_listing_dates(&self, listing_dates: KnownListingDates) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>>;
+}
+
+/// Versioned schema of the configured database, see [`crate::integrations::clickhouse::migrations::MIGRATIONS`]
+pub trait SchemaMigrationsService {
+    /// Applies the pending migrations in order and returns their versions.
+    /// Nothing is applied while an applied migration was edited or is unknown to this version.
+    fn migrate(&self) -> BoxFuture<'_, Result<Vec<u32>, ClickhouseRepositoryError>>;
+
+    /// Every known and recorded migration, ordered by version
+    fn migration_status(&self) -> BoxFuture<'_, Result<Vec<MigrationStatus>, ClickhouseRepositoryError>>;
+
+    /// Copies the candlesticks stored in the table of the `from` layout into the table of the configured one,
+    /// so switching the layout keeps the history. Copying twice is harmless, the replacing engine merges the duplicates.
+    fn copy_candlesticks(&self, from: StorageLayout) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+
+    #[test]
+    fn test_filter_matches_every_non_empty_predicate() {
+        // given
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let eth_btc = Symbol::from_pool(Binance, "ETH".to_string(), "BTC".to_string());
+        let sol_usdt = Symbol::from_pool(Binance, "SOL".to_string(), "USDT".to_string());
+        let assets = CandlesticksFilter::default()
+            .with_base_assets(vec!["BTC".to_string(), "ETH".to_string()])
+            .with_quote_assets(vec!["USDT".to_string()]);
+        let symbols = CandlesticksFilter::default().with_exchanges(vec![Binance]).with_symbols(vec![eth_btc.clone()]);
+
+        // then
+        assert!(CandlesticksFilter::default().matches(&sol_usdt));
+        assert!(assets.matches(&btc_usdt));
+        assert!(!assets.matches(&eth_btc));
+        assert!(!assets.matches(&sol_usdt));
+        assert!(symbols.matches(&eth_btc));
+        assert!(!symbols.matches(&btc_usdt));
+    }
 }
@@ -0,0 +1,73 @@
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksRepository, DownloadCheckpointsService, JobId, PlannedWindow, WindowCheckpoints};
+use crate::integrations::clickhouse::model::download_checkpoint_row::DownloadCheckpointRow;
+use crate::integrations::clickhouse::model::download_job_row::DownloadJobRow;
+use crate::integrations::clickhouse::model::job_window_row::JobWindowRow;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use futures_util::FutureExt;
+use futures_util::future::BoxFuture;
+use time::OffsetDateTime;
+
+impl DownloadCheckpointsService for CandlesticksRepository {
+    fn create_job(&self, job_id: JobId, windows: Vec<CandlesticksWindow>) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
+        async move {
+            let created_at = OffsetDateTime::now_utc();
+            let mut insert = self.client.insert(&self.tables.download_jobs_table())?;
+            for window in windows.iter() {
+                insert.write(&DownloadJobRow::new(job_id, created_at, window)).await?;
+            }
+            insert.end().await?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn commit_checkpoints(&self, job_id: JobId, checkpoints: WindowCheckpoints) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
+        async move {
+            let mut insert = self.client.insert(&self.tables.download_checkpoints_table())?;
+            for ((symbol, timeframe, window_start), last_open_time) in checkpoints.iter() {
+                insert
+                    .write(&DownloadCheckpointRow::new(job_id, symbol, timeframe, *window_start, *last_open_time))
+                    .await?;
+            }
+            insert.end().await?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn fetch_remaining_windows(&self, job_id: JobId) -> BoxFuture<'_, Result<Vec<PlannedWindow>, ClickhouseRepositoryError>> {
+        let query = format!(
+            r#"
+            SELECT
+                j.exchange_type,
+                j.base_asset,
+                j.quote_asset,
+                j.timeframe,
+                j.window_start,
+                j.window_end,
+                c.last_open_time
+            FROM {} AS j
+            LEFT JOIN (
+                SELECT exchange_type, base_asset, quote_asset, timeframe, window_start, max(last_open_time) AS last_open_time
+                FROM {}
+                WHERE job_id = ?
+                GROUP BY exchange_type, base_asset, quote_asset, timeframe, window_start
+            ) AS c USING (exchange_type, base_asset, quote_asset, timeframe, window_start)
+            WHERE j.job_id = ?
+            ORDER BY j.base_asset, j.quote_asset, j.timeframe, j.window_start
+            SETTINGS join_use_nulls = 1
+        "#,
+            self.tables.download_jobs_table(),
+            self.tables.download_checkpoints_table()
+        );
+
+        async move {
+            let rows = self.client.query(&query).bind(job_id).bind(job_id).fetch_all::<JobWindowRow>().await?;
+
+            let windows = rows.into_iter().map(JobWindowRow::to_remaining_window).collect::<Result<Vec<_>, _>>()?;
+            Ok(windows.into_iter().flatten().collect())
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,37 @@
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksRepository, KnownListingDates, ListingDatesService};
+use crate::integrations::clickhouse::model::listing_date_row::ListingDateRow;
+use futures_util::FutureExt;
+use futures_util::future::BoxFuture;
+use time::OffsetDateTime;
+
+impl ListingDatesService for CandlesticksRepository {
+    fn fetch_listing_dates(&self) -> BoxFuture<'_, Result<KnownListingDates, ClickhouseRepositoryError>> {
+        let query = format!(
+            r#"
+            SELECT exchange_type, base_asset, quote_asset, timeframe, listed_at, discovered_at
+            FROM {} FINAL
+        "#,
+            self.tables.listing_dates_table()
+        );
+
+        async move {
+            let rows = self.client.query(&query).fetch_all::<ListingDateRow>().await?;
+            rows.into_iter().map(ListingDateRow::to_entry).collect()
+        }
+        .boxed()
+    }
+
+    fn save_listing_dates(&self, listing_dates: KnownListingDates) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
+        async move {
+            let discovered_at = OffsetDateTime::now_utc();
+            let mut insert = self.client.insert(&self.tables.listing_dates_table())?;
+            for ((symbol, timeframe), listed_at) in listing_dates.iter() {
+                insert.write(&ListingDateRow::new(symbol, timeframe, *listed_at, discovered_at)).await?;
+            }
+            insert.end().await?;
+            Ok(())
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,86 @@
+use crate::config::StorageLayout;
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksRepository, SchemaMigrationsService};
+use crate::integrations::clickhouse::migrations::{CREATE_SCHEMA_MIGRATIONS, MIGRATIONS, MigrationState, MigrationStatus, migration_statuses};
+use crate::integrations::clickhouse::model::schema_migration_row::SchemaMigrationRow;
+use futures_util::FutureExt;
+use futures_util::future::BoxFuture;
+use time::OffsetDateTime;
+use tracing::info;
+
+impl CandlesticksRepository {
+    async fn fetch_applied_migrations(&self) -> Result<Vec<SchemaMigrationRow>, ClickhouseRepositoryError> {
+        for statement in CREATE_SCHEMA_MIGRATIONS {
+            self.client.query(&self.tables.render(statement)).execute().await?;
+        }
+
+        // concurrent runs may record a step twice, the replacing engine merges them eventually
+        let query = format!(
+            r#"
+            SELECT version, name, checksum, applied_at
+            FROM {} FINAL
+            ORDER BY version
+        "#,
+            self.tables.schema_migrations_table()
+        );
+        Ok(self.client.query(&query).fetch_all::<SchemaMigrationRow>().await?)
+    }
+}
+
+impl SchemaMigrationsService for CandlesticksRepository {
+    fn migrate(&self) -> BoxFuture<'_, Result<Vec<u32>, ClickhouseRepositoryError>> {
+        async move {
+            let statuses = migration_statuses(MIGRATIONS, self.fetch_applied_migrations().await?);
+            if let Some(MigrationStatus { version, name, state }) = statuses.iter().find(|status| status.is_conflict()).cloned() {
+                return Err(match state {
+                    MigrationState::Unknown => ClickhouseRepositoryError::UnknownMigration { version, name },
+                    _ => ClickhouseRepositoryError::MigrationChecksumMismatch { version, name },
+                });
+            }
+
+            let mut applied = vec![];
+            for status in statuses.iter().filter(|status| status.state == MigrationState::Pending) {
+                let migration = MIGRATIONS.iter().find(|migration| migration.version == status.version).unwrap();
+                for statement in migration.statements {
+                    self.client.query(&self.tables.render(statement)).execute().await?;
+                }
+
+                let mut insert = self.client.insert(&self.tables.schema_migrations_table())?;
+                insert
+                    .write(&SchemaMigrationRow {
+                        version: migration.version,
+                        name: migration.name.to_string(),
+                        checksum: migration.checksum(),
+                        applied_at: OffsetDateTime::now_utc(),
+                    })
+                    .await?;
+                insert.end().await?;
+                info!("[CandlesticksRepository] applied migration {} ({})", migration.version, migration.name);
+                applied.push(migration.version);
+            }
+            Ok(applied)
+        }
+        .boxed()
+    }
+
+    fn migration_status(&self) -> BoxFuture<'_, Result<Vec<MigrationStatus>, ClickhouseRepositoryError>> {
+        async move { Ok(migration_statuses(MIGRATIONS, self.fetch_applied_migrations().await?)) }.boxed()
+    }
+
+    fn copy_candlesticks(&self, from: StorageLayout) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
+        let (source, target) = (self.layout_table(from), self.candlesticks_table());
+        let columns = "exchange_type, base_asset, quote_asset, timeframe, open_time, close_time, \
+                       open_price, close_price, low_price, high_price, volume, is_closed";
+        let query = format!("INSERT INTO {target} ({columns}) SELECT {columns} FROM {source}");
+
+        async move {
+            if source == target {
+                return Ok(());
+            }
+            self.client.query(&query).execute().await?;
+            info!("[CandlesticksRepository] copied the candlesticks of {} into {}", source, target);
+            Ok(())
+        }
+        .boxed()
+    }
+}
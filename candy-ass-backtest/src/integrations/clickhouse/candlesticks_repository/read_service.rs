@@ -1,12 +1,21 @@
-use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksReadService, CandlesticksRepository};
+use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksFilter, CandlesticksReadService, CandlesticksRepository, LastOpenTimes};
 use crate::integrations::clickhouse::model::candlestick_row::CandlestickRow;
+use crate::integrations::clickhouse::model::gap_row::GapRow;
+use crate::integrations::clickhouse::model::last_open_time_row::LastOpenTimeRow;
+use crate::integrations::clickhouse::model::stored_range_row::StoredRangeRow;
 use crate::integrations::clickhouse::{ClickhouseRepositoryError, format_clickhouse_date};
 use candy_ass_core::domain::candlestick::Candlestick;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::domain::exchange_type::ExchangeType;
+use candy_ass_core::domain::symbol::Symbol;
 use candy_ass_core::domain::timeframe::Timeframe;
+use clickhouse::query::Query;
 use futures_util::FutureExt;
 use futures_util::future::BoxFuture;
 use rayon::prelude::IntoParallelIterator;
 use rayon::prelude::ParallelIterator;
+use std::collections::HashMap;
+use std::sync::Arc;
 use time::OffsetDateTime;
 use tracing::error;
 
@@ -16,9 +25,12 @@ impl CandlesticksReadService for CandlesticksRepository {
         timeframes: Vec<Timeframe>,
         from: OffsetDateTime,
         to: OffsetDateTime,
-    ) -> BoxFuture<Result<Vec<Candlestick>, ClickhouseRepositoryError>> {
+        include_unclosed: bool,
+        filter: CandlesticksFilter,
+    ) -> BoxFuture<'_, Result<Vec<Candlestick>, ClickhouseRepositoryError>> {
         let client = self.client.clone();
-        let query = r#"
+        let query = format!(
+            r#"
             SELECT
                 exchange_type,
                 base_asset,
@@ -30,39 +42,388 @@ impl CandlesticksReadService for CandlesticksRepository {
                 close_price,
                 low_price,
                 high_price,
-                volume
-            FROM `candy_ass`.candlesticks
+                volume,
+                is_closed
+            FROM {}
             WHERE
                 timeframe IN ? AND
                 open_time >= ? AND
-                open_time < ?
+                open_time < ? AND
+                (? OR (is_closed AND close_time < now())){}
             ORDER BY open_time ASC
-        "#
-        .to_string();
+        "#,
+            self.candlesticks_table(),
+            filter_predicates(&filter)
+        );
 
         async move {
-            let rows = client
+            let query = client
                 .query(&query)
                 .bind(timeframes)
                 .bind(format_clickhouse_date(from))
                 .bind(format_clickhouse_date(to))
+                .bind(include_unclosed);
+            let rows = bind_filter(query, &filter)
                 .fetch_all::<CandlestickRow>()
                 .await
                 .map_err(ClickhouseRepositoryError::from)?;
 
-            let result = tokio_rayon::spawn(move || {
-                rows.into_par_iter()
-                    .map(|row| {
-                        row.to_candlestick().inspect_err(|err| {
-                            error!("Candlestick conversion error: {:?}", err);
-                        })
-                    })
-                    .filter_map(|row| row.ok())
-                    .collect::<Vec<_>>()
-            })
-            .await;
-            Ok(result)
+            Ok(to_candlesticks(rows).await)
+        }
+        .boxed()
+    }
+
+    fn fetch_symbol_candlesticks(
+        &self,
+        symbol: Arc<Symbol>,
+        timeframe: Timeframe,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        include_unclosed: bool,
+    ) -> BoxFuture<'_, Result<Vec<Candlestick>, ClickhouseRepositoryError>> {
+        let client = self.client.clone();
+        // served by the sort key in the symbol-first layout and only the months of the range are read
+        let query = format!(
+            r#"
+            SELECT
+                exchange_type,
+                base_asset,
+                quote_asset,
+                timeframe,
+                open_time,
+                close_time,
+                open_price,
+                close_price,
+                low_price,
+                high_price,
+                volume,
+                is_closed
+            FROM {}
+            WHERE
+                exchange_type = ? AND
+                base_asset = ? AND
+                quote_asset = ? AND
+                timeframe = ? AND
+                open_time >= ? AND
+                open_time < ? AND
+                (? OR (is_closed AND close_time < now()))
+            ORDER BY open_time ASC
+        "#,
+            self.candlesticks_table()
+        );
+
+        async move {
+            let rows = client
+                .query(&query)
+                .bind(symbol.exchange_type.to_string())
+                .bind(&symbol.base_asset)
+                .bind(&symbol.quote_asset)
+                .bind(timeframe)
+                .bind(format_clickhouse_date(from))
+                .bind(format_clickhouse_date(to))
+                .bind(include_unclosed)
+                .fetch_all::<CandlestickRow>()
+                .await
+                .map_err(ClickhouseRepositoryError::from)?;
+
+            Ok(to_candlesticks(rows).await)
         }
         .boxed()
     }
+
+    fn fetch_last_open_times(&self, timeframes: Vec<Timeframe>) -> BoxFuture<'_, Result<LastOpenTimes, ClickhouseRepositoryError>> {
+        let client = self.client.clone();
+        let query = format!(
+            r#"
+            SELECT
+                exchange_type,
+                base_asset,
+                quote_asset,
+                timeframe,
+                max(open_time) AS open_time
+            FROM {}
+            WHERE
+                timeframe IN ?
+            GROUP BY exchange_type, base_asset, quote_asset, timeframe
+        "#,
+            self.candlesticks_table()
+        );
+
+        async move {
+            let rows = client
+                .query(&query)
+                .bind(timeframes)
+                .fetch_all::<LastOpenTimeRow>()
+                .await
+                .map_err(ClickhouseRepositoryError::from)?;
+
+            rows.into_iter().map(LastOpenTimeRow::to_entry).collect()
+        }
+        .boxed()
+    }
+
+    fn fetch_gaps(
+        &self,
+        symbols: Vec<Arc<Symbol>>,
+        timeframe: Timeframe,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> BoxFuture<'_, Result<Vec<CandlesticksWindow>, ClickhouseRepositoryError>> {
+        let client = self.client.clone();
+        let cadence = timeframe.duration().whole_seconds();
+        let gaps_query = format!(
+            r#"
+            SELECT
+                exchange_type,
+                base_asset,
+                quote_asset,
+                timeframe,
+                previous_open_time,
+                open_time
+            FROM (
+                SELECT
+                    exchange_type,
+                    base_asset,
+                    quote_asset,
+                    timeframe,
+                    open_time,
+                    lagInFrame(open_time) OVER (
+                        PARTITION BY exchange_type, base_asset, quote_asset, timeframe
+                        ORDER BY open_time ASC
+                        ROWS BETWEEN 1 PRECEDING AND CURRENT ROW
+                    ) AS previous_open_time
+                FROM (
+                    SELECT DISTINCT exchange_type, base_asset, quote_asset, timeframe, open_time
+                    FROM {}
+                    WHERE
+                        timeframe = ? AND
+                        open_time >= ? AND
+                        open_time < ? AND
+                        (exchange_type, base_asset, quote_asset) IN ?
+                )
+            )
+            WHERE
+                previous_open_time != toDateTime(0) AND
+                dateDiff('second', previous_open_time, open_time) > ?
+        "#,
+            self.candlesticks_table()
+        );
+        let ranges_query = format!(
+            r#"
+            SELECT
+                exchange_type,
+                base_asset,
+                quote_asset,
+                min(open_time) AS first_open_time,
+                max(open_time) AS last_open_time
+            FROM {}
+            WHERE
+                timeframe = ? AND
+                open_time >= ? AND
+                open_time < ? AND
+                (exchange_type, base_asset, quote_asset) IN ?
+            GROUP BY exchange_type, base_asset, quote_asset
+        "#,
+            self.candlesticks_table()
+        );
+
+        async move {
+            if symbols.is_empty() {
+                return Ok(Vec::new());
+            }
+            let symbol_keys = symbols
+                .iter()
+                .map(|symbol| (symbol.exchange_type.to_string(), symbol.base_asset.as_str(), symbol.quote_asset.as_str()))
+                .collect::<Vec<_>>();
+
+            let gap_rows = client
+                .query(&gaps_query)
+                .bind(&timeframe)
+                .bind(format_clickhouse_date(from))
+                .bind(format_clickhouse_date(to))
+                .bind(&symbol_keys)
+                .bind(cadence)
+                .fetch_all::<GapRow>()
+                .await
+                .map_err(ClickhouseRepositoryError::from)?;
+            let stored_ranges = client
+                .query(&ranges_query)
+                .bind(&timeframe)
+                .bind(format_clickhouse_date(from))
+                .bind(format_clickhouse_date(to))
+                .bind(&symbol_keys)
+                .fetch_all::<StoredRangeRow>()
+                .await
+                .map_err(ClickhouseRepositoryError::from)?
+                .into_iter()
+                .map(StoredRangeRow::to_entry)
+                .collect::<Result<HashMap<_, _>, _>>()?;
+
+            let mut gaps = gap_rows.into_iter().map(GapRow::to_window).collect::<Result<Vec<_>, _>>()?;
+            gaps.extend(edge_gaps(&symbols, &timeframe, from, to, &stored_ranges));
+            gaps.sort_by(|a, b| (&a.symbol.base_asset, &a.symbol.quote_asset, a.start).cmp(&(&b.symbol.base_asset, &b.symbol.quote_asset, b.start)));
+            Ok(gaps)
+        }
+        .boxed()
+    }
+}
+
+/// Ranges of `from..to` outside of the stored candlesticks of every symbol: before the first one, after the last one,
+/// or the whole range when nothing is stored. An edge shorter than a candlestick is not a gap.
+fn edge_gaps(
+    symbols: &[Arc<Symbol>],
+    timeframe: &Timeframe,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+    stored_ranges: &HashMap<Arc<Symbol>, (OffsetDateTime, OffsetDateTime)>,
+) -> Vec<CandlesticksWindow> {
+    let cadence = timeframe.duration();
+    symbols
+        .iter()
+        .flat_map(|symbol| {
+            let window = |start, end| CandlesticksWindow::new(symbol.clone(), timeframe.clone(), start, Some(end));
+            match stored_ranges.get(symbol) {
+                None if from + cadence <= to => vec![window(from, to)],
+                None => vec![],
+                Some((first_open_time, last_open_time)) => {
+                    let leading = (*first_open_time - cadence >= from).then(|| window(from, *first_open_time - cadence));
+                    let trailing = (*last_open_time + cadence * 2 <= to).then(|| window(*last_open_time + cadence, to));
+                    leading.into_iter().chain(trailing).collect()
+                }
+            }
+        })
+        .collect()
+}
+
+/// `AND`-ed predicates of the non-empty filter lists, their values are bound by [`bind_filter`] in the same order
+fn filter_predicates(filter: &CandlesticksFilter) -> String {
+    let mut predicates = String::new();
+    if !filter.exchanges.is_empty() {
+        predicates.push_str(" AND exchange_type IN ?");
+    }
+    if !filter.base_assets.is_empty() {
+        predicates.push_str(" AND base_asset IN ?");
+    }
+    if !filter.quote_assets.is_empty() {
+        predicates.push_str(" AND quote_asset IN ?");
+    }
+    if !filter.symbols.is_empty() {
+        predicates.push_str(" AND (exchange_type, base_asset, quote_asset) IN ?");
+    }
+    predicates
+}
+
+fn bind_filter(mut query: Query, filter: &CandlesticksFilter) -> Query {
+    if !filter.exchanges.is_empty() {
+        query = query.bind(filter.exchanges.iter().map(ExchangeType::to_string).collect::<Vec<_>>());
+    }
+    if !filter.base_assets.is_empty() {
+        query = query.bind(&filter.base_assets);
+    }
+    if !filter.quote_assets.is_empty() {
+        query = query.bind(&filter.quote_assets);
+    }
+    if !filter.symbols.is_empty() {
+        let symbols = filter
+            .symbols
+            .iter()
+            .map(|symbol| (symbol.exchange_type.to_string(), symbol.base_asset.as_str(), symbol.quote_asset.as_str()))
+            .collect::<Vec<_>>();
+        query = query.bind(symbols);
+    }
+    query
+}
+
+/// Conversion runs on the rayon pool, rows that can't be converted are logged and left out
+async fn to_candlesticks(rows: Vec<CandlestickRow>) -> Vec<Candlestick> {
+    tokio_rayon::spawn(move || {
+        rows.into_par_iter()
+            .map(|row| {
+                row.to_candlestick().inspect_err(|err| {
+                    error!("Candlestick conversion error: {:?}", err);
+                })
+            })
+            .filter_map(|row| row.ok())
+            .collect::<Vec<_>>()
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::timeframe::Timeframe::OneHour;
+    use time::Duration;
+
+    fn btc_usdt() -> Arc<Symbol> {
+        Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string())
+    }
+
+    fn range() -> (OffsetDateTime, OffsetDateTime) {
+        let from = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+        (from, from + Duration::hours(10))
+    }
+
+    #[test]
+    fn test_leading_range_is_a_gap() {
+        // given
+        let (from, to) = range();
+        let stored = HashMap::from([(btc_usdt(), (from + Duration::hours(3), to - Duration::hours(1)))]);
+
+        // when
+        let gaps = edge_gaps(&[btc_usdt()], &OneHour, from, to, &stored);
+
+        // then
+        assert_eq!(1, gaps.len());
+        assert_eq!(from, gaps[0].start);
+        assert_eq!(Some(from + Duration::hours(2)), gaps[0].end);
+    }
+
+    #[test]
+    fn test_trailing_range_is_a_gap() {
+        // given
+        let (from, to) = range();
+        let stored = HashMap::from([(btc_usdt(), (from, from + Duration::hours(6)))]);
+
+        // when
+        let gaps = edge_gaps(&[btc_usdt()], &OneHour, from, to, &stored);
+
+        // then
+        assert_eq!(1, gaps.len());
+        assert_eq!(from + Duration::hours(7), gaps[0].start);
+        assert_eq!(Some(to), gaps[0].end);
+    }
+
+    #[test]
+    fn test_symbol_without_rows_is_a_whole_range_gap() {
+        // given
+        let (from, to) = range();
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let stored = HashMap::from([(btc_usdt(), (from, to - Duration::hours(1)))]);
+
+        // when
+        let gaps = edge_gaps(&[btc_usdt(), eth_usdt.clone()], &OneHour, from, to, &stored);
+
+        // then
+        assert_eq!(1, gaps.len());
+        assert_eq!(eth_usdt, gaps[0].symbol);
+        assert_eq!(from, gaps[0].start);
+        assert_eq!(Some(to), gaps[0].end);
+    }
+
+    #[test]
+    fn test_only_non_empty_filter_lists_are_pushed_down() {
+        // given
+        let filter = CandlesticksFilter::default()
+            .with_base_assets(vec!["BTC".to_string(), "ETH".to_string()])
+            .with_symbols(vec![Symbol::from_pool(Binance, "SOL".to_string(), "USDT".to_string())]);
+
+        // when
+        let predicates = filter_predicates(&filter);
+
+        // then
+        assert_eq!("", filter_predicates(&CandlesticksFilter::default()));
+        assert_eq!(" AND base_asset IN ? AND (exchange_type, base_asset, quote_asset) IN ?", predicates);
+    }
 }
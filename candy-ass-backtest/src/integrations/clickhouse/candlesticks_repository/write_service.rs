@@ -1,50 +1,20 @@
+use crate::config::StorageLayout;
 use crate::integrations::clickhouse::ClickhouseRepositoryError;
-use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksRepository, CandlesticksWriteService};
+use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksRepository, CandlesticksWriteService, SchemaMigrationsService};
 use crate::integrations::clickhouse::model::candlestick_row::CandlestickRow;
 use candy_ass_core::domain::candlestick::Candlestick;
 use futures_util::future::BoxFuture;
 use futures_util::{FutureExt, StreamExt, TryFutureExt};
+use tracing::info;
 
 impl CandlesticksWriteService for CandlesticksRepository {
-    fn init(&self) -> BoxFuture<Result<(), ClickhouseRepositoryError>> {
-        futures::future::ready(())
-            .then(|_| {
-                let create_database_query = "CREATE DATABASE IF NOT EXISTS `candy_ass`";
-                self.client.query(create_database_query).execute()
-            })
-            .and_then(|_| {
-                let create_table_query = r#"
-                        CREATE TABLE IF NOT EXISTS `candy_ass`.candlesticks
-                        (
-                            exchange_type LowCardinality(String),
-                            base_asset LowCardinality(String),
-                            quote_asset LowCardinality(String),
-                            timeframe LowCardinality(String),
-                            open_time DateTime,
-                            close_time DateTime,
-                            open_price Float64,
-                            close_price Float64,
-                            low_price Float64,
-                            high_price Float64,
-                            volume Float64
-                        )
-                        ENGINE = ReplacingMergeTree(volume)
-                        PRIMARY KEY (open_time, timeframe, exchange_type, base_asset, quote_asset)
-                        ORDER BY (open_time, timeframe, exchange_type, base_asset, quote_asset)
-                        SETTINGS index_granularity = 8192;
-                "#;
-                self.client.query(create_table_query).execute()
-            })
-            .map_err(ClickhouseRepositoryError::from)
-            .boxed()
+    fn init(&self) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
+        self.migrate().map_ok(|_| ()).boxed()
     }
 
-    fn bulk_insert_candlesticks(&self, chunk: Vec<Vec<Candlestick>>) -> BoxFuture<Result<(), ClickhouseRepositoryError>> {
+    fn bulk_insert_candlesticks(&self, chunk: Vec<Vec<Candlestick>>) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
         async move {
-            let mut insert = self
-                .client
-                .insert("`candy_ass`.candlesticks")
-                .expect("[CandlesticksRepository] failed to open insert statement for clickhouse");
+            let mut insert = self.client.insert(&self.candlesticks_table())?;
 
             let rows = tokio_stream::iter(chunk)
                 .flat_map_unordered(8, |candlesticks| tokio_stream::iter(candlesticks).map(|x| CandlestickRow::from(&x)))
@@ -52,22 +22,48 @@ impl CandlesticksWriteService for CandlesticksRepository {
                 .await;
 
             for row in rows {
-                insert.write(&row).await.expect("[CandlesticksRepository] failed to append candlestick row");
+                insert.write(&row).await?;
             }
 
-            insert
-                .end()
-                .await
-                .expect("[CandlesticksRepository] failed to complete bulk insert for candlesticks table");
+            insert.end().await?;
             Ok(())
         }
         .boxed()
     }
 
-    fn run_optimization(&self) -> BoxFuture<Result<(), ClickhouseRepositoryError>> {
+    fn run_optimization(&self) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
         async move {
-            let optimization_query = "OPTIMIZE TABLE `candy_ass`.candlesticks FINAL";
-            self.client.query(optimization_query).execute().await.map_err(ClickhouseRepositoryError::from)
+            match self.layout {
+                StorageLayout::TimeFirst => {
+                    let optimization_query = format!("OPTIMIZE TABLE {} FINAL", self.candlesticks_table());
+                    self.client.query(&optimization_query).execute().await?;
+                }
+                // merged months are left alone, usually only the recent ones have new parts
+                StorageLayout::SymbolFirst => {
+                    let partitions_query = r#"
+                        SELECT partition_id
+                        FROM system.parts
+                        WHERE database = ? AND table = ? AND active
+                        GROUP BY partition_id
+                        HAVING count() > 1
+                        ORDER BY partition_id
+                    "#;
+                    let partitions = self
+                        .client
+                        .query(partitions_query)
+                        .bind(&self.tables.database)
+                        .bind(&self.tables.candlesticks_by_symbol)
+                        .fetch_all::<String>()
+                        .await?;
+
+                    let optimization_query = format!("OPTIMIZE TABLE {} PARTITION ID ? FINAL", self.candlesticks_table());
+                    for partition in partitions {
+                        self.client.query(&optimization_query).bind(&partition).execute().await?;
+                        info!("[CandlesticksRepository] optimized partition {}", partition);
+                    }
+                }
+            }
+            Ok(())
         }
         .boxed()
     }
@@ -0,0 +1,323 @@
+use crate::integrations::clickhouse::model::schema_migration_row::SchemaMigrationRow;
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+
+/// Versioned DDL step of the candlesticks database. Steps are applied in `version` order and recorded
+/// in `schema_migrations` along with the checksum of their statements.
+/// Statements are templates, the names of the database and the tables are placeholders, see [`crate::config::TableNames::render`].
+/// An applied step must never be edited, a schema change is a new step appended to [`MIGRATIONS`].
+#[derive(Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub statements: &'static [&'static str],
+}
+
+impl Migration {
+    /// FNV-1a of the statement templates, whitespace is normalized so reformatting a step doesn't change it.
+    /// It doesn't depend on the configured names.
+    pub fn checksum(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+
+        self.statements
+            .iter()
+            .flat_map(|statement| statement.split_whitespace().chain(std::iter::once(";")))
+            .flat_map(|token| token.bytes().chain(std::iter::once(b' ')))
+            .fold(OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+    }
+}
+
+/// Bootstraps the bookkeeping, it is not a migration itself
+pub(crate) const CREATE_SCHEMA_MIGRATIONS: [&str; 2] = [
+    "CREATE DATABASE IF NOT EXISTS {database}",
+    r#"
+        CREATE TABLE IF NOT EXISTS {schema_migrations}
+        (
+            version UInt32,
+            name String,
+            checksum UInt64,
+            applied_at DateTime
+        )
+        ENGINE = ReplacingMergeTree(applied_at)
+        ORDER BY version
+    "#,
+];
+
+/// Every step is idempotent, so installations created before the migrations existed are adopted by replaying them
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_candlesticks",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS {candlesticks}
+            (
+                exchange_type LowCardinality(String),
+                base_asset LowCardinality(String),
+                quote_asset LowCardinality(String),
+                timeframe LowCardinality(String),
+                open_time DateTime,
+                close_time DateTime,
+                open_price Float64,
+                close_price Float64,
+                low_price Float64,
+                high_price Float64,
+                volume Float64
+            )
+            ENGINE = ReplacingMergeTree(volume)
+            PRIMARY KEY (open_time, timeframe, exchange_type, base_asset, quote_asset)
+            ORDER BY (open_time, timeframe, exchange_type, base_asset, quote_asset)
+            SETTINGS index_granularity = 8192
+        "#],
+    },
+    Migration {
+        version: 2,
+        name: "add_candlesticks_is_closed",
+        // rows stored before the flag existed are taken as closed
+        statements: &["ALTER TABLE {candlesticks} ADD COLUMN IF NOT EXISTS is_closed Bool DEFAULT true"],
+    },
+    Migration {
+        version: 3,
+        name: "create_download_jobs",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS {download_jobs}
+            (
+                job_id UInt64,
+                created_at DateTime,
+                exchange_type LowCardinality(String),
+                base_asset LowCardinality(String),
+                quote_asset LowCardinality(String),
+                timeframe LowCardinality(String),
+                window_start DateTime,
+                window_end Nullable(DateTime)
+            )
+            ENGINE = MergeTree
+            ORDER BY (job_id, timeframe, exchange_type, base_asset, quote_asset)
+        "#],
+    },
+    Migration {
+        version: 4,
+        name: "create_download_checkpoints",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS {download_checkpoints}
+            (
+                job_id UInt64,
+                exchange_type LowCardinality(String),
+                base_asset LowCardinality(String),
+                quote_asset LowCardinality(String),
+                timeframe LowCardinality(String),
+                last_open_time DateTime
+            )
+            ENGINE = ReplacingMergeTree(last_open_time)
+            ORDER BY (job_id, timeframe, exchange_type, base_asset, quote_asset)
+        "#],
+    },
+    Migration {
+        version: 5,
+        name: "create_candlesticks_by_symbol",
+        // the projection keeps the time-slice replays off full scans, the replacing engine needs it rebuilt on merges
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS {candlesticks_by_symbol}
+            (
+                exchange_type LowCardinality(String),
+                base_asset LowCardinality(String),
+                quote_asset LowCardinality(String),
+                timeframe LowCardinality(String),
+                open_time DateTime,
+                close_time DateTime,
+                open_price Float64,
+                close_price Float64,
+                low_price Float64,
+                high_price Float64,
+                volume Float64,
+                is_closed Bool DEFAULT true,
+                PROJECTION by_time (SELECT * ORDER BY (timeframe, open_time))
+            )
+            ENGINE = ReplacingMergeTree(volume)
+            PARTITION BY toYYYYMM(open_time)
+            ORDER BY (exchange_type, base_asset, quote_asset, timeframe, open_time)
+            SETTINGS index_granularity = 8192, deduplicate_merge_projection_mode = 'rebuild'
+        "#],
+    },
+    Migration {
+        version: 6,
+        name: "add_download_checkpoints_window_start",
+        // a job may hold several windows of a pair, e.g. backfilled gaps, each of them gets its own checkpoint.
+        // Checkpoints committed before have no window, their jobs resume from the window starts.
+        statements: &[r#"
+            ALTER TABLE {download_checkpoints}
+                ADD COLUMN IF NOT EXISTS window_start DateTime DEFAULT toDateTime(0) AFTER timeframe,
+                MODIFY ORDER BY (job_id, timeframe, exchange_type, base_asset, quote_asset, window_start)
+        "#],
+    },
+    Migration {
+        version: 7,
+        name: "create_listing_dates",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS {listing_dates}
+            (
+                exchange_type LowCardinality(String),
+                base_asset LowCardinality(String),
+                quote_asset LowCardinality(String),
+                timeframe LowCardinality(String),
+                listed_at DateTime,
+                discovered_at DateTime
+            )
+            ENGINE = ReplacingMergeTree(discovered_at)
+            ORDER BY (exchange_type, base_asset, quote_asset, timeframe)
+        "#],
+    },
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationState {
+    Applied(OffsetDateTime),
+    Pending,
+    /// Applied, but the step was edited since, the database may not match it
+    ChecksumMismatch {
+        recorded: u64,
+        expected: u64,
+    },
+    /// Recorded by a newer version of the application
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationStatus {
+    pub version: u32,
+    pub name: String,
+    pub state: MigrationState,
+}
+
+impl MigrationStatus {
+    /// Migrating stops at it, see [`MigrationState::ChecksumMismatch`] and [`MigrationState::Unknown`]
+    pub fn is_conflict(&self) -> bool {
+        matches!(self.state, MigrationState::ChecksumMismatch { .. } | MigrationState::Unknown)
+    }
+}
+
+/// Compares the known steps with the recorded ones, ordered by version
+pub fn migration_statuses(migrations: &[Migration], applied: Vec<SchemaMigrationRow>) -> Vec<MigrationStatus> {
+    let mut applied = applied.into_iter().map(|row| (row.version, row)).collect::<BTreeMap<_, _>>();
+
+    let mut statuses = migrations
+        .iter()
+        .map(|migration| {
+            let state = match applied.remove(&migration.version) {
+                None => MigrationState::Pending,
+                Some(row) if row.checksum == migration.checksum() => MigrationState::Applied(row.applied_at),
+                Some(row) => MigrationState::ChecksumMismatch {
+                    recorded: row.checksum,
+                    expected: migration.checksum(),
+                },
+            };
+            MigrationStatus {
+                version: migration.version,
+                name: migration.name.to_string(),
+                state,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    statuses.extend(applied.into_values().map(|row| MigrationStatus {
+        version: row.version,
+        name: row.name,
+        state: MigrationState::Unknown,
+    }));
+    statuses.sort_by_key(|status| status.version);
+    statuses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEPS: &[Migration] = &[
+        Migration {
+            version: 1,
+            name: "first",
+            statements: &["CREATE TABLE t (a UInt8) ENGINE = Memory"],
+        },
+        Migration {
+            version: 2,
+            name: "second",
+            statements: &["ALTER TABLE t ADD COLUMN b UInt8"],
+        },
+    ];
+
+    fn row(migration: &Migration, checksum: u64) -> SchemaMigrationRow {
+        SchemaMigrationRow {
+            version: migration.version,
+            name: migration.name.to_string(),
+            checksum,
+            applied_at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn test_migrations_are_ordered_and_unique() {
+        // Then
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+    }
+
+    #[test]
+    fn test_checksum_ignores_formatting() {
+        // Given
+        let formatted = Migration {
+            version: 1,
+            name: "first",
+            statements: &["CREATE TABLE t\n    (a UInt8)\n    ENGINE = Memory  "],
+        };
+        let edited = Migration {
+            version: 1,
+            name: "first",
+            statements: &["CREATE TABLE t (a UInt16) ENGINE = Memory"],
+        };
+
+        // Then
+        assert_eq!(STEPS[0].checksum(), formatted.checksum());
+        assert_ne!(STEPS[0].checksum(), edited.checksum());
+    }
+
+    #[test]
+    fn test_statuses_of_applied_pending_and_edited_steps() {
+        // Given
+        let applied = vec![row(&STEPS[0], STEPS[0].checksum())];
+        let edited = vec![row(&STEPS[0], 42)];
+
+        // When
+        let statuses = migration_statuses(STEPS, applied);
+        let edited_statuses = migration_statuses(STEPS, edited);
+
+        // Then
+        assert_eq!(MigrationState::Applied(OffsetDateTime::UNIX_EPOCH), statuses[0].state);
+        assert_eq!(MigrationState::Pending, statuses[1].state);
+        assert!(!statuses.iter().any(MigrationStatus::is_conflict));
+        assert_eq!(
+            MigrationState::ChecksumMismatch {
+                recorded: 42,
+                expected: STEPS[0].checksum()
+            },
+            edited_statuses[0].state
+        );
+    }
+
+    #[test]
+    fn test_steps_recorded_by_a_newer_version_are_unknown() {
+        // Given
+        let newer = Migration {
+            version: 3,
+            name: "third",
+            statements: &[],
+        };
+        let applied = STEPS.iter().chain([&newer]).map(|migration| row(migration, migration.checksum())).collect();
+
+        // When
+        let statuses = migration_statuses(STEPS, applied);
+
+        // Then
+        assert_eq!(3, statuses.len());
+        assert_eq!(MigrationState::Unknown, statuses[2].state);
+        assert!(statuses[2].is_conflict());
+    }
+}
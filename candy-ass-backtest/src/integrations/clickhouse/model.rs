@@ -1 +1,9 @@
 pub mod candlestick_row;
+pub mod download_checkpoint_row;
+pub mod download_job_row;
+pub mod gap_row;
+pub mod job_window_row;
+pub mod last_open_time_row;
+pub mod listing_date_row;
+pub mod schema_migration_row;
+pub mod stored_range_row;
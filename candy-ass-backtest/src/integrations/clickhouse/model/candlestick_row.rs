@@ -20,6 +20,7 @@ pub struct CandlestickRow {
     pub low_price: f64,
     pub high_price: f64,
     pub volume: f64,
+    pub is_closed: bool,
 }
 
 #[derive(Debug, Row, Serialize, Deserialize)]
@@ -44,6 +45,7 @@ impl CandlestickRow {
             low_price: self.low_price,
             high_price: self.high_price,
             volume: self.volume,
+            is_closed: self.is_closed,
         })
     }
 }
@@ -62,6 +64,7 @@ impl From<&Candlestick> for CandlestickRow {
             low_price: src.low_price,
             high_price: src.high_price,
             volume: src.volume,
+            is_closed: src.is_closed,
         }
     }
 }
@@ -89,6 +92,7 @@ mod tests {
             low_price: 9950.0,
             high_price: 10200.0,
             volume: 0.25,
+            is_closed: true,
         }
     }
 
@@ -104,6 +108,7 @@ mod tests {
             low_price: 9950.0,
             high_price: 10200.0,
             volume: 0.25,
+            is_closed: false,
         }
     }
 
@@ -130,6 +135,7 @@ mod tests {
         assert_eq!(row.timeframe, "1m");
         assert_eq!(row.open_price, 10000.0);
         assert_eq!(row.close_price, 10100.0);
+        assert!(!row.is_closed);
     }
 
     #[test]
@@ -0,0 +1,34 @@
+use crate::integrations::clickhouse::candlesticks_repository::JobId;
+use candy_ass_core::domain::symbol::Symbol;
+use candy_ass_core::domain::timeframe::Timeframe;
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Latest persisted candlestick of a window within a download job, the window is identified by its planned start
+#[derive(Debug, Row, Serialize, Deserialize)]
+pub struct DownloadCheckpointRow {
+    pub job_id: JobId,
+    pub exchange_type: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub timeframe: String,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub window_start: OffsetDateTime,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub last_open_time: OffsetDateTime,
+}
+
+impl DownloadCheckpointRow {
+    pub fn new(job_id: JobId, symbol: &Symbol, timeframe: &Timeframe, window_start: OffsetDateTime, last_open_time: OffsetDateTime) -> Self {
+        Self {
+            job_id,
+            exchange_type: symbol.exchange_type.to_string(),
+            base_asset: symbol.base_asset.clone(),
+            quote_asset: symbol.quote_asset.clone(),
+            timeframe: timeframe.to_string(),
+            window_start,
+            last_open_time,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::integrations::clickhouse::candlesticks_repository::JobId;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// One planned window of a download job
+#[derive(Debug, Row, Serialize, Deserialize)]
+pub struct DownloadJobRow {
+    pub job_id: JobId,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub created_at: OffsetDateTime,
+    pub exchange_type: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub timeframe: String,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub window_start: OffsetDateTime,
+    #[serde(with = "clickhouse::serde::time::datetime::option")]
+    pub window_end: Option<OffsetDateTime>,
+}
+
+impl DownloadJobRow {
+    pub fn new(job_id: JobId, created_at: OffsetDateTime, window: &CandlesticksWindow) -> Self {
+        Self {
+            job_id,
+            created_at,
+            exchange_type: window.symbol.exchange_type.to_string(),
+            base_asset: window.symbol.base_asset.clone(),
+            quote_asset: window.symbol.quote_asset.clone(),
+            timeframe: window.timeframe.to_string(),
+            window_start: window.start,
+            window_end: window.end,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::domain::symbol::Symbol;
+use candy_ass_core::domain::timeframe::Timeframe;
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Two consecutive stored candlesticks that are further apart than the timeframe cadence
+#[derive(Debug, Row, Serialize, Deserialize)]
+pub struct GapRow {
+    pub exchange_type: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub timeframe: String,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub previous_open_time: OffsetDateTime,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub open_time: OffsetDateTime,
+}
+
+impl GapRow {
+    pub fn to_window(self) -> Result<CandlesticksWindow, ClickhouseRepositoryError> {
+        let exchange_type = self.exchange_type.as_str().try_into()?;
+        let symbol = Symbol::from_pool(exchange_type, self.base_asset, self.quote_asset);
+        let timeframe: Timeframe = self.timeframe.as_str().try_into()?;
+        let cadence = timeframe.duration();
+        Ok(CandlesticksWindow::new(
+            symbol,
+            timeframe,
+            self.previous_open_time + cadence,
+            Some(self.open_time - cadence),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::timeframe::Timeframe::OneHour;
+    use time::Duration;
+
+    #[test]
+    fn test_to_window() {
+        let previous_open_time = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+        let row = GapRow {
+            exchange_type: "Binance".to_string(),
+            base_asset: "BTC".to_string(),
+            quote_asset: "USDT".to_string(),
+            timeframe: "1h".to_string(),
+            previous_open_time,
+            open_time: previous_open_time + Duration::hours(4),
+        };
+
+        let window = row.to_window().unwrap();
+
+        assert_eq!(Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string()), window.symbol);
+        assert_eq!(OneHour, window.timeframe);
+        assert_eq!(previous_open_time + Duration::hours(1), window.start);
+        assert_eq!(Some(previous_open_time + Duration::hours(3)), window.end);
+    }
+}
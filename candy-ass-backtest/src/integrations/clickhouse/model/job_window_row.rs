@@ -0,0 +1,82 @@
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use crate::integrations::clickhouse::candlesticks_repository::PlannedWindow;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::domain::symbol::Symbol;
+use candy_ass_core::domain::timeframe::Timeframe;
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Planned window of a download job joined with its checkpoint
+#[derive(Debug, Row, Serialize, Deserialize)]
+pub struct JobWindowRow {
+    pub exchange_type: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub timeframe: String,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub window_start: OffsetDateTime,
+    #[serde(with = "clickhouse::serde::time::datetime::option")]
+    pub window_end: Option<OffsetDateTime>,
+    #[serde(with = "clickhouse::serde::time::datetime::option")]
+    pub last_open_time: Option<OffsetDateTime>,
+}
+
+impl JobWindowRow {
+    /// Part of the window after its checkpoint along with the planned start, `None` when the window is fully persisted
+    pub fn to_remaining_window(self) -> Result<Option<PlannedWindow>, ClickhouseRepositoryError> {
+        let exchange_type = self.exchange_type.as_str().try_into()?;
+        let symbol = Symbol::from_pool(exchange_type, self.base_asset, self.quote_asset);
+        let timeframe: Timeframe = self.timeframe.as_str().try_into()?;
+        let start = match self.last_open_time {
+            Some(last_open_time) => self.window_start.max(last_open_time + timeframe.duration()),
+            None => self.window_start,
+        };
+
+        let remaining = self.window_end.is_none_or(|end| start <= end);
+        Ok(remaining.then(|| (self.window_start, CandlesticksWindow::new(symbol, timeframe, start, self.window_end))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Duration;
+
+    fn row(window_end: Option<OffsetDateTime>, last_open_time: Option<OffsetDateTime>) -> JobWindowRow {
+        JobWindowRow {
+            exchange_type: "Binance".to_string(),
+            base_asset: "BTC".to_string(),
+            quote_asset: "USDT".to_string(),
+            timeframe: "1h".to_string(),
+            window_start: OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap(),
+            window_end,
+            last_open_time,
+        }
+    }
+
+    #[test]
+    fn test_to_remaining_window() {
+        let start = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+        let end = start + Duration::days(1);
+
+        let (_, untouched) = row(Some(end), None).to_remaining_window().unwrap().unwrap();
+        assert_eq!(start, untouched.start);
+
+        let (planned_start, resumed) = row(Some(end), Some(start + Duration::hours(5))).to_remaining_window().unwrap().unwrap();
+        assert_eq!(start, planned_start);
+        assert_eq!(start + Duration::hours(6), resumed.start);
+        assert_eq!(Some(end), resumed.end);
+
+        let (_, open_ended) = row(None, Some(start + Duration::days(3))).to_remaining_window().unwrap().unwrap();
+        assert_eq!(start + Duration::days(3) + Duration::hours(1), open_ended.start);
+    }
+
+    #[test]
+    fn test_to_remaining_window_completed() {
+        let start = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+        let end = start + Duration::days(1);
+
+        assert!(row(Some(end), Some(end)).to_remaining_window().unwrap().is_none());
+    }
+}
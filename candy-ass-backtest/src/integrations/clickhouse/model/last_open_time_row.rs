@@ -0,0 +1,63 @@
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use candy_ass_core::domain::symbol::Symbol;
+use candy_ass_core::domain::timeframe::Timeframe;
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+#[derive(Debug, Row, Serialize, Deserialize)]
+pub struct LastOpenTimeRow {
+    pub exchange_type: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub timeframe: String,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub open_time: OffsetDateTime,
+}
+
+impl LastOpenTimeRow {
+    pub fn to_entry(self) -> Result<((Arc<Symbol>, Timeframe), OffsetDateTime), ClickhouseRepositoryError> {
+        let exchange_type = self.exchange_type.as_str().try_into()?;
+        let symbol = Symbol::from_pool(exchange_type, self.base_asset, self.quote_asset);
+        let timeframe = self.timeframe.as_str().try_into()?;
+        Ok(((symbol, timeframe), self.open_time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::timeframe::Timeframe::OneHour;
+
+    #[test]
+    fn test_to_entry() {
+        let row = LastOpenTimeRow {
+            exchange_type: "Binance".to_string(),
+            base_asset: "BTC".to_string(),
+            quote_asset: "USDT".to_string(),
+            timeframe: "1h".to_string(),
+            open_time: OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap(),
+        };
+
+        let ((symbol, timeframe), open_time) = row.to_entry().unwrap();
+
+        assert_eq!(Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string()), symbol);
+        assert_eq!(OneHour, timeframe);
+        assert_eq!(OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap(), open_time);
+    }
+
+    #[test]
+    fn test_to_entry_unknown_timeframe() {
+        let row = LastOpenTimeRow {
+            exchange_type: "Binance".to_string(),
+            base_asset: "BTC".to_string(),
+            quote_asset: "USDT".to_string(),
+            timeframe: "7m".to_string(),
+            open_time: OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap(),
+        };
+
+        assert!(matches!(row.to_entry(), Err(ClickhouseRepositoryError::ParsingError(_))));
+    }
+}
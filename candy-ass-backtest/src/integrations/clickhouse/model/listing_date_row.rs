@@ -0,0 +1,40 @@
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use candy_ass_core::domain::symbol::Symbol;
+use candy_ass_core::domain::timeframe::Timeframe;
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+/// `open_time` of the first candlestick the exchange lists for a (symbol, timeframe)
+#[derive(Debug, Row, Serialize, Deserialize)]
+pub struct ListingDateRow {
+    pub exchange_type: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub timeframe: String,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub listed_at: OffsetDateTime,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub discovered_at: OffsetDateTime,
+}
+
+impl ListingDateRow {
+    pub fn new(symbol: &Symbol, timeframe: &Timeframe, listed_at: OffsetDateTime, discovered_at: OffsetDateTime) -> Self {
+        Self {
+            exchange_type: symbol.exchange_type.to_string(),
+            base_asset: symbol.base_asset.clone(),
+            quote_asset: symbol.quote_asset.clone(),
+            timeframe: timeframe.to_string(),
+            listed_at,
+            discovered_at,
+        }
+    }
+
+    pub fn to_entry(self) -> Result<((Arc<Symbol>, Timeframe), OffsetDateTime), ClickhouseRepositoryError> {
+        let exchange_type = self.exchange_type.as_str().try_into()?;
+        let symbol = Symbol::from_pool(exchange_type, self.base_asset, self.quote_asset);
+        let timeframe = self.timeframe.as_str().try_into()?;
+        Ok(((symbol, timeframe), self.listed_at))
+    }
+}
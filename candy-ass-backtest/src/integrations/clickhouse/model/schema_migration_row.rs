@@ -0,0 +1,13 @@
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Migration recorded as applied in `schema_migrations`
+#[derive(Debug, Row, Serialize, Deserialize)]
+pub struct SchemaMigrationRow {
+    pub version: u32,
+    pub name: String,
+    pub checksum: u64,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub applied_at: OffsetDateTime,
+}
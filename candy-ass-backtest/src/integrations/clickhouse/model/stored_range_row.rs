@@ -0,0 +1,26 @@
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use candy_ass_core::domain::symbol::Symbol;
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+/// First and last stored `open_time` of a symbol within a range of a single timeframe
+#[derive(Debug, Row, Serialize, Deserialize)]
+pub struct StoredRangeRow {
+    pub exchange_type: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub first_open_time: OffsetDateTime,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub last_open_time: OffsetDateTime,
+}
+
+impl StoredRangeRow {
+    pub fn to_entry(self) -> Result<(Arc<Symbol>, (OffsetDateTime, OffsetDateTime)), ClickhouseRepositoryError> {
+        let exchange_type = self.exchange_type.as_str().try_into()?;
+        let symbol = Symbol::from_pool(exchange_type, self.base_asset, self.quote_asset);
+        Ok((symbol, (self.first_open_time, self.last_open_time)))
+    }
+}
@@ -0,0 +1,291 @@
+use crate::integrations::clickhouse::ClickhouseRepositoryError;
+use crate::integrations::clickhouse::candlesticks_repository::CandlesticksWriteService;
+use candy_ass_core::domain::candlestick::Candlestick;
+use futures_util::FutureExt;
+use futures_util::future::BoxFuture;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+/// A segment file is closed once it grows above it, a new batch goes into the next one
+pub const DEFAULT_SEGMENT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+/// Time between two replay attempts while the store is unavailable
+pub const DEFAULT_REPLAY_INTERVAL: Duration = Duration::from_secs(10);
+const SEGMENT_EXTENSION: &str = "segment";
+
+/// Write-ahead spool in front of a candlesticks store. Batches the store fails to insert are appended to segment files
+/// and replayed in order once it is reachable again, from a later insert or from `init` of the next run.
+/// Batches are delivered at least once, a replay interrupted by a failure starts the segment over,
+/// which the replacing engine of the candlesticks table deduplicates.
+pub struct SpooledWriteService {
+    inner: Arc<dyn CandlesticksWriteService + Send + Sync>,
+    directory: PathBuf,
+    segment_max_bytes: u64,
+    replay_interval: Duration,
+    /// Serializes appends and replays, so segments are written and replayed in order
+    last_replay: Mutex<Option<Instant>>,
+}
+
+impl SpooledWriteService {
+    pub fn new(inner: Arc<dyn CandlesticksWriteService + Send + Sync>, directory: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            directory: directory.into(),
+            segment_max_bytes: DEFAULT_SEGMENT_MAX_BYTES,
+            replay_interval: DEFAULT_REPLAY_INTERVAL,
+            last_replay: Mutex::new(None),
+        }
+    }
+
+    pub fn with_segment_max_bytes(mut self, segment_max_bytes: u64) -> Self {
+        self.segment_max_bytes = segment_max_bytes.max(1);
+        self
+    }
+
+    pub fn with_replay_interval(mut self, replay_interval: Duration) -> Self {
+        self.replay_interval = replay_interval;
+        self
+    }
+
+    /// Segment files waiting for a replay, oldest first
+    pub async fn segments(&self) -> Result<Vec<PathBuf>, ClickhouseRepositoryError> {
+        let mut entries = match fs::read_dir(&self.directory).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut segments = vec![];
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|extension| extension == SEGMENT_EXTENSION) {
+                segments.push(path);
+            }
+        }
+        // names are zero-padded sequence numbers
+        segments.sort();
+        Ok(segments)
+    }
+
+    /// Inserts every spooled batch into the store, a segment is deleted once all of its batches are stored.
+    /// Returns the number of replayed batches, the first failure stops the replay.
+    pub async fn replay(&self) -> Result<usize, ClickhouseRepositoryError> {
+        let mut last_replay = self.last_replay.lock().await;
+        *last_replay = Some(Instant::now());
+        self.replay_segments().await
+    }
+
+    async fn replay_segments(&self) -> Result<usize, ClickhouseRepositoryError> {
+        let mut replayed = 0;
+        for segment in self.segments().await? {
+            let content = fs::read_to_string(&segment).await?;
+            for line in content.lines().filter(|line| !line.is_empty()) {
+                // a torn line is the batch that was being written when the process died, it was never acknowledged
+                let Ok(chunk) = serde_json::from_str::<Vec<Vec<Candlestick>>>(line) else {
+                    warn!("[SpooledWriteService] skipped a torn batch of {}", segment.display());
+                    continue;
+                };
+                self.inner.bulk_insert_candlesticks(chunk).await?;
+                replayed += 1;
+            }
+            fs::remove_file(&segment).await?;
+            info!("[SpooledWriteService] replayed {}", segment.display());
+        }
+        Ok(replayed)
+    }
+
+    async fn append(&self, chunk: &[Vec<Candlestick>]) -> Result<(), ClickhouseRepositoryError> {
+        fs::create_dir_all(&self.directory).await?;
+        let segment = match self.segments().await?.pop() {
+            Some(last) if fs::metadata(&last).await?.len() < self.segment_max_bytes => last,
+            Some(last) => self.segment_path(Self::sequence(&last) + 1),
+            None => self.segment_path(0),
+        };
+
+        // the leading separator ends a torn line of a crashed write, the batch never continues it
+        let mut line = vec![b'\n'];
+        line.extend(serde_json::to_vec(chunk).map_err(std::io::Error::from)?);
+        line.push(b'\n');
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(&segment).await?;
+        file.write_all(&line).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+
+    fn segment_path(&self, sequence: u64) -> PathBuf {
+        self.directory.join(format!("{:020}.{}", sequence, SEGMENT_EXTENSION))
+    }
+
+    fn sequence(segment: &Path) -> u64 {
+        segment
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl CandlesticksWriteService for SpooledWriteService {
+    /// Batches spooled by a previous run are replayed right after the store is initialized
+    fn init(&self) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
+        async move {
+            self.inner.init().await?;
+            let _ = self
+                .replay()
+                .await
+                .inspect(|replayed| {
+                    if *replayed > 0 {
+                        info!("[SpooledWriteService] replayed {} spooled batches", replayed)
+                    }
+                })
+                .inspect_err(|err| error!("[SpooledWriteService] failed to replay the spool: {}", err));
+            Ok(())
+        }
+        .boxed()
+    }
+
+    /// Fails only when the batch could neither be inserted nor spooled
+    fn bulk_insert_candlesticks(&self, chunk: Vec<Vec<Candlestick>>) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
+        async move {
+            let mut last_replay = self.last_replay.lock().await;
+            if !self.segments().await?.is_empty() {
+                // newer batches wait behind the spooled ones until a replay gets through
+                if last_replay.is_some_and(|last_replay| last_replay.elapsed() < self.replay_interval) {
+                    return self.append(&chunk).await;
+                }
+                *last_replay = Some(Instant::now());
+                if let Err(err) = self.replay_segments().await {
+                    warn!("[SpooledWriteService] store is still unavailable: {}", err);
+                    return self.append(&chunk).await;
+                }
+            }
+
+            match self.inner.bulk_insert_candlesticks(chunk.clone()).await {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    warn!("[SpooledWriteService] spooling a batch of {} pages: {}", chunk.len(), err);
+                    *last_replay = Some(Instant::now());
+                    self.append(&chunk)
+                        .await
+                        .inspect_err(|spool_err| error!("[SpooledWriteService] failed to spool a batch: {}", spool_err))
+                        .map_err(|_| err)
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn run_optimization(&self) -> BoxFuture<'_, Result<(), ClickhouseRepositoryError>> {
+        self.inner.run_optimization()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mocks::mock_clickhouse::MockClickhouse;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::symbol::Symbol;
+    use candy_ass_core::domain::timeframe::Timeframe::OneHour;
+    use clickhouse::error::Error::Network;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use time::OffsetDateTime;
+
+    fn spool_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("candy-ass-spool-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn chunk(open_time: OffsetDateTime) -> Vec<Vec<Candlestick>> {
+        vec![vec![Candlestick {
+            symbol: Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string()),
+            timeframe: OneHour,
+            open_time,
+            close_time: open_time + time::Duration::hours(1) - time::Duration::milliseconds(1),
+            open_price: 1.0,
+            close_price: 1.0,
+            low_price: 1.0,
+            high_price: 1.0,
+            volume: 1.0,
+            is_closed: true,
+        }]]
+    }
+
+    #[tokio::test]
+    async fn test_failed_batches_are_spooled_and_replayed() {
+        let available = Arc::new(AtomicBool::new(false));
+        let inserted = Arc::new(AtomicUsize::new(0));
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse.expect_bulk_insert_candlesticks().returning({
+            let available = available.clone();
+            let inserted = inserted.clone();
+            move |chunk| {
+                let result = match available.load(Ordering::SeqCst) {
+                    true => {
+                        inserted.fetch_add(chunk.len(), Ordering::SeqCst);
+                        Ok(())
+                    }
+                    false => Err(ClickhouseRepositoryError::UnexpectedResult(Network("connection refused".into()))),
+                };
+                Box::pin(async move { result })
+            }
+        });
+        let directory = spool_directory("replay");
+        let spool = SpooledWriteService::new(Arc::new(clickhouse), &directory)
+            .with_segment_max_bytes(1)
+            .with_replay_interval(Duration::ZERO);
+
+        // the store is down, both batches end up on disk in their own segment
+        spool.bulk_insert_candlesticks(chunk(OffsetDateTime::UNIX_EPOCH)).await.unwrap();
+        spool
+            .bulk_insert_candlesticks(chunk(OffsetDateTime::UNIX_EPOCH + time::Duration::hours(1)))
+            .await
+            .unwrap();
+        assert_eq!(2, spool.segments().await.unwrap().len());
+        assert_eq!(0, inserted.load(Ordering::SeqCst));
+
+        // the next insert replays the spool first
+        available.store(true, Ordering::SeqCst);
+        spool
+            .bulk_insert_candlesticks(chunk(OffsetDateTime::UNIX_EPOCH + time::Duration::hours(2)))
+            .await
+            .unwrap();
+        assert!(spool.segments().await.unwrap().is_empty());
+        assert_eq!(3, inserted.load(Ordering::SeqCst));
+
+        let _ = std::fs::remove_dir_all(directory);
+    }
+
+    #[tokio::test]
+    async fn test_torn_batch_is_skipped() {
+        let inserted = Arc::new(AtomicUsize::new(0));
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse.expect_bulk_insert_candlesticks().returning({
+            let inserted = inserted.clone();
+            move |chunk| {
+                inserted.fetch_add(chunk.len(), Ordering::SeqCst);
+                Box::pin(async { Ok(()) })
+            }
+        });
+        let directory = spool_directory("torn");
+        let spool = SpooledWriteService::new(Arc::new(clickhouse), &directory);
+        spool.append(&chunk(OffsetDateTime::UNIX_EPOCH)).await.unwrap();
+        let segment = spool.segments().await.unwrap().pop().unwrap();
+        let mut file = fs::OpenOptions::new().append(true).open(&segment).await.unwrap();
+        file.write_all(b"[[{\"symbol\":").await.unwrap();
+        file.flush().await.unwrap();
+        spool.append(&chunk(OffsetDateTime::UNIX_EPOCH + time::Duration::hours(1))).await.unwrap();
+
+        assert_eq!(2, spool.replay().await.unwrap());
+        assert_eq!(2, inserted.load(Ordering::SeqCst));
+        assert!(spool.segments().await.unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(directory);
+    }
+}
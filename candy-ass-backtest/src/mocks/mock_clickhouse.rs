@@ -1,9 +1,15 @@
 use crate::integrations::clickhouse::ClickhouseRepositoryError;
-use crate::integrations::clickhouse::candlesticks_repository::{CandlesticksReadService, CandlesticksWriteService};
+use crate::integrations::clickhouse::candlesticks_repository::{
+    CandlesticksFilter, CandlesticksReadService, CandlesticksWriteService, DownloadCheckpointsService, JobId, KnownListingDates, LastOpenTimes,
+    ListingDatesService, PlannedWindow, WindowCheckpoints,
+};
 use candy_ass_core::domain::candlestick::Candlestick;
+use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+use candy_ass_core::domain::symbol::Symbol;
 use candy_ass_core::domain::timeframe::Timeframe;
 use futures_util::future::BoxFuture;
 use mockall::mock;
+use std::sync::Arc;
 use time::OffsetDateTime;
 
 mock! {
@@ -22,6 +28,34 @@ mock! {
             &self,
             timeframe: Vec<Timeframe>,
             from: OffsetDateTime,
-            to: OffsetDateTime,) -> BoxFuture<'static, Result<Vec<Candlestick>, ClickhouseRepositoryError>>;
+            to: OffsetDateTime,
+            include_unclosed: bool,
+            filter: CandlesticksFilter,
+        ) -> BoxFuture<'static, Result<Vec<Candlestick>, ClickhouseRepositoryError>>;
+        fn fetch_last_open_times(&self, timeframes: Vec<Timeframe>) -> BoxFuture<'static, Result<LastOpenTimes, ClickhouseRepositoryError>>;
+        fn fetch_symbol_candlesticks(
+            &self,
+            symbol: Arc<Symbol>,
+            timeframe: Timeframe,
+            from: OffsetDateTime,
+            to: OffsetDateTime,
+            include_unclosed: bool,
+        ) -> BoxFuture<'static, Result<Vec<Candlestick>, ClickhouseRepositoryError>>;
+        fn fetch_gaps(
+            &self,
+            symbols: Vec<Arc<Symbol>>,
+            timeframe: Timeframe,
+            from: OffsetDateTime,
+            to: OffsetDateTime,
+        ) -> BoxFuture<'static, Result<Vec<CandlesticksWindow>, ClickhouseRepositoryError>>;
+    }
+    impl DownloadCheckpointsService for Clickhouse {
+        fn create_job(&self, job_id: JobId, windows: Vec<CandlesticksWindow>) -> BoxFuture<'static, Result<(), ClickhouseRepositoryError>>;
+        fn commit_checkpoints(&self, job_id: JobId, checkpoints: WindowCheckpoints) -> BoxFuture<'static, Result<(), ClickhouseRepositoryError>>;
+        fn fetch_remaining_windows(&self, job_id: JobId) -> BoxFuture<'static, Result<Vec<PlannedWindow>, ClickhouseRepositoryError>>;
+    }
+    impl ListingDatesService for Clickhouse {
+        fn fetch_listing_dates(&self) -> BoxFuture<'static, Result<KnownListingDates, ClickhouseRepositoryError>>;
+        fn save_listing_dates(&self, listing_dates: KnownListingDates) -> BoxFuture<'static, Result<(), ClickhouseRepositoryError>>;
     }
 }
@@ -1,3 +1,7 @@
 #[path = "application/actors.rs"]
 mod actors;
 mod config;
+#[path = "application/test_history_downloader.rs"]
+mod test_history_downloader;
+#[path = "application/test_streams.rs"]
+mod test_streams;
@@ -1,22 +1,65 @@
 #[cfg(test)]
 mod tests {
     use actix::Actor;
-    use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::CandlesticksDownloaderActor;
+    use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::commands::backfill_candlesticks::BackfillCandlesticks;
+    use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::commands::cancel_download::CancelDownload;
     use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::commands::download_candlesticks::DownloadCandlesticks;
+    use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::commands::resume_job::ResumeJob;
     use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::commands::shutdown::Command::Shutdown;
     use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::errors::DownloadHistoryError;
+    use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::progress::WindowState;
+    use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::queries::{
+        GetLifecycleReceiver, GetProgress, GetProgressReceiver, ListJobs, PlanDownload,
+    };
+    use candy_ass_backtest::application::history_downloader::candlesticks_downloader_actor::{CandlesticksDownloaderActor, PartialCandlePolicy};
+    use candy_ass_backtest::application::job_queue::{JobReceiver, JobStatus};
+    use candy_ass_backtest::integrations::clickhouse::candlesticks_repository::{KnownListingDates, LastOpenTimes};
+    use candy_ass_backtest::mocks::mock_clickhouse::MockClickhouse;
+    use candy_ass_core::application::supervision::{LifecycleEvent, RestartPolicy};
     use candy_ass_core::domain::candlestick::Candlestick;
+    use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
     use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
     use candy_ass_core::domain::symbol::Symbol;
-    use candy_ass_core::domain::timeframe::Timeframe::ThreeMinutes;
+    use candy_ass_core::domain::timeframe::Timeframe::{OneHour, ThreeMinutes};
     use candy_ass_core::integrations::binance_spot_client;
-    use candy_ass_core::integrations::http::binance::BINANCE_RATE_LIMIT;
+    use candy_ass_core::integrations::http::binance::{BINANCE_HEADER_USED_WEIGHT_1M, BINANCE_RATE_LIMIT};
+    use candy_ass_core::mocks::mock_binance_spot::broken::fake_http_error;
+    use candy_ass_core::mocks::mock_binance_spot::default::{DEFAULT_BINANCE_SPOT_CLIENT, fake_candlesticks};
+    use candy_ass_core::mocks::mock_binance_spot::{HEADER_MAP, MockBinanceSpotClient};
+    use candy_ass_core::utils::RetryPolicy;
     use reqwest::Client;
-    use std::sync::Arc;
+    use reqwest::header::HeaderValue;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use time::format_description::well_known::Rfc3339;
     use time::{Duration, OffsetDateTime};
     use tokio_stream::StreamExt;
     use tokio_stream::wrappers::ReceiverStream;
 
+    /// Serves one candlestick per page, so a job spans many requests
+    fn one_by_one_binance_client() -> Arc<MockBinanceSpotClient> {
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client
+            .expect_fetch_candlesticks()
+            .returning(|symbol, timeframe, _, start_time, end_time| Box::pin(fake_candlesticks(symbol, timeframe, 1, start_time, end_time)));
+        Arc::new(binance_client)
+    }
+
+    fn btc_and_eth_download() -> DownloadCandlesticks {
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+
+        DownloadCandlesticks {
+            symbols: Arc::new(vec![btc_usdt, eth_usdt]),
+            timeframes: vec![(ThreeMinutes, start_date)],
+            end_date: None,
+            filter: Arc::new(|_| true),
+            resume_from: Arc::new(LastOpenTimes::new()),
+        }
+    }
+
     #[actix::test]
     async fn test_history_streaming_actor() {
         // Given
@@ -28,19 +71,901 @@ mod tests {
 
         let msg = DownloadCandlesticks {
             symbols: Arc::new(vec![btc_usdt]),
-            timeframe: ThreeMinutes,
-            start_date,
+            timeframes: vec![(ThreeMinutes, start_date)],
+            end_date: None,
             filter: Arc::new(|symbol| symbol.quote_asset == "USDT"),
+            resume_from: Arc::new(LastOpenTimes::new()),
         };
 
         // When
-        let receiver = history_streaming_actor.send(msg.clone()).await.unwrap().unwrap();
-        let err = history_streaming_actor.send(msg.clone()).await.unwrap().unwrap_err();
-        assert_eq!(err, DownloadHistoryError::ActorIsBusy);
+        let first = history_streaming_actor.send(msg.clone()).await.unwrap().unwrap();
+        let second = history_streaming_actor.send(msg.clone()).await.unwrap().unwrap();
+        assert_ne!(first.job_id, second.job_id);
 
-        let result = ReceiverStream::new(receiver).collect::<Vec<Vec<Candlestick>>>().await;
+        let result = ReceiverStream::new(first.receiver)
+            .map(|(_, page)| page)
+            .collect::<Vec<Vec<Candlestick>>>()
+            .await;
+        let queued_result = ReceiverStream::new(second.receiver)
+            .map(|(_, page)| page)
+            .collect::<Vec<Vec<Candlestick>>>()
+            .await;
 
         assert_eq!(2, result.len());
+        assert_eq!(2, queued_result.len());
         history_streaming_actor.send(Shutdown).await.unwrap();
     }
+
+    #[actix::test]
+    async fn test_download_resumes_from_last_stored_candlestick() {
+        // Given
+        let binance_client = DEFAULT_BINANCE_SPOT_CLIENT.clone();
+        let actor = CandlesticksDownloaderActor::new(10, 2, binance_client, 0).start();
+
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let btc_last_open_time = OffsetDateTime::parse("2025-06-29T18:30:00Z", &Rfc3339).unwrap();
+
+        let msg = DownloadCandlesticks {
+            symbols: Arc::new(vec![btc_usdt.clone(), eth_usdt.clone()]),
+            timeframes: vec![(ThreeMinutes, start_date)],
+            end_date: None,
+            filter: Arc::new(|_| true),
+            resume_from: Arc::new(LastOpenTimes::from([((btc_usdt.clone(), ThreeMinutes), btc_last_open_time)])),
+        };
+
+        // When
+        let receiver = actor.send(msg).await.unwrap().unwrap().receiver;
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        let btc = result.iter().flatten().filter(|candlestick| candlestick.symbol == btc_usdt).collect::<Vec<_>>();
+        let eth = result.iter().flatten().filter(|candlestick| candlestick.symbol == eth_usdt).collect::<Vec<_>>();
+
+        assert_eq!(4, btc.len());
+        assert_eq!(btc_last_open_time, btc[0].open_time);
+        assert_eq!(30, eth.len());
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_backfill_fetches_only_given_windows() {
+        // Given
+        let binance_client = DEFAULT_BINANCE_SPOT_CLIENT.clone();
+        let actor = CandlesticksDownloaderActor::new(10, 2, binance_client, 0).start();
+
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let gap_start = OffsetDateTime::parse("2025-06-29T17:30:00Z", &Rfc3339).unwrap();
+        let gap_end = OffsetDateTime::parse("2025-06-29T17:42:00Z", &Rfc3339).unwrap();
+
+        let msg = BackfillCandlesticks {
+            windows: vec![CandlesticksWindow::new(btc_usdt.clone(), ThreeMinutes, gap_start, Some(gap_end))],
+        };
+
+        // When
+        let receiver = actor.send(msg).await.unwrap().unwrap().receiver;
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        let candlesticks = result.into_iter().flatten().collect::<Vec<_>>();
+
+        assert_eq!(5, candlesticks.len());
+        assert_eq!(gap_start, candlesticks[0].open_time);
+        assert_eq!(gap_end, candlesticks[4].open_time);
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_download_stops_at_end_date() {
+        // Given
+        let binance_client = DEFAULT_BINANCE_SPOT_CLIENT.clone();
+        let actor = CandlesticksDownloaderActor::new(10, 2, binance_client, 0).start();
+
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let end_date = OffsetDateTime::parse("2025-06-29T17:20:00Z", &Rfc3339).unwrap();
+
+        let msg = DownloadCandlesticks {
+            symbols: Arc::new(vec![btc_usdt]),
+            timeframes: vec![(ThreeMinutes, start_date)],
+            end_date: Some(end_date),
+            filter: Arc::new(|_| true),
+            resume_from: Arc::new(LastOpenTimes::new()),
+        };
+
+        // When
+        let receiver = actor.send(msg).await.unwrap().unwrap().receiver;
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        let candlesticks = result.into_iter().flatten().collect::<Vec<_>>();
+
+        assert_eq!(3, candlesticks.len());
+        assert!(candlesticks.iter().all(|candlestick| candlestick.open_time <= end_date));
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_download_several_timeframes_in_one_job() {
+        // Given
+        let binance_client = DEFAULT_BINANCE_SPOT_CLIENT.clone();
+        let actor = CandlesticksDownloaderActor::new(10, 2, binance_client, 0).start();
+
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let three_minutes_start = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let one_hour_start = OffsetDateTime::parse("2025-06-29T18:00:00Z", &Rfc3339).unwrap();
+
+        let msg = DownloadCandlesticks {
+            symbols: Arc::new(vec![btc_usdt]),
+            timeframes: vec![(ThreeMinutes, three_minutes_start), (OneHour, one_hour_start)],
+            end_date: None,
+            filter: Arc::new(|_| true),
+            resume_from: Arc::new(LastOpenTimes::new()),
+        };
+
+        // When
+        let receiver = actor.send(msg).await.unwrap().unwrap().receiver;
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        let candlesticks = result.into_iter().flatten().collect::<Vec<_>>();
+        let three_minutes = candlesticks.iter().filter(|candlestick| candlestick.timeframe == ThreeMinutes).count();
+        let one_hour = candlesticks.iter().filter(|candlestick| candlestick.timeframe == OneHour).count();
+
+        assert_eq!(30, three_minutes);
+        assert_eq!(14, one_hour);
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_download_progress_is_published() {
+        // Given
+        let binance_client = DEFAULT_BINANCE_SPOT_CLIENT.clone();
+        let actor = CandlesticksDownloaderActor::new(10, 2, binance_client, 0).start();
+
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let end_date = OffsetDateTime::parse("2025-06-29T18:39:00Z", &Rfc3339).unwrap();
+
+        let msg = DownloadCandlesticks {
+            symbols: Arc::new(vec![btc_usdt, eth_usdt]),
+            timeframes: vec![(ThreeMinutes, start_date)],
+            end_date: Some(end_date),
+            filter: Arc::new(|_| true),
+            resume_from: Arc::new(LastOpenTimes::new()),
+        };
+
+        // When
+        let JobReceiver { job_id, receiver } = actor.send(msg).await.unwrap().unwrap();
+        let mut progress_receiver = actor.send(GetProgressReceiver(job_id)).await.unwrap().unwrap();
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+        let finished = progress_receiver.wait_for(|progress| progress.is_finished()).await.unwrap().clone();
+
+        // Then
+        let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+
+        assert_eq!(60, result.into_iter().flatten().count());
+        assert_eq!(2, progress.count(&WindowState::Done));
+        assert_eq!(60, progress.candles_fetched());
+        assert_eq!(Some(9999), progress.used_weight_1m);
+        assert!(progress.windows.iter().all(|window| window.last_fetched == Some(end_date)));
+        assert_eq!(Some(Duration::ZERO), progress.eta(end_date));
+        assert_eq!(finished.candles_fetched(), progress.candles_fetched());
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_cancel_flushes_fetched_pages() {
+        // Given
+        let actor = CandlesticksDownloaderActor::new(1, 1, one_by_one_binance_client(), 0).start();
+        assert!(!actor.send(CancelDownload { job_id: None }).await.unwrap());
+
+        // When
+        let JobReceiver { job_id, receiver } = actor.send(btc_and_eth_download()).await.unwrap().unwrap();
+        let mut candlesticks = ReceiverStream::new(receiver).map(|(_, page)| page);
+        let first_page = candlesticks.next().await.unwrap();
+        assert!(actor.send(CancelDownload { job_id: Some(job_id) }).await.unwrap());
+        let rest = candlesticks.collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        let received = first_page.len() + rest.into_iter().flatten().count();
+        let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+
+        assert!(received < 60);
+        assert_eq!(received, progress.candles_fetched());
+        assert_eq!(2, progress.count(&WindowState::Cancelled));
+        assert!(progress.is_finished());
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_shutdown_waits_for_running_job() {
+        // Given
+        let actor = CandlesticksDownloaderActor::new(1, 1, one_by_one_binance_client(), 0).start();
+        let receiver = actor.send(btc_and_eth_download()).await.unwrap().unwrap().receiver;
+        let mut candlesticks = ReceiverStream::new(receiver).map(|(_, page)| page);
+        candlesticks.next().await.unwrap();
+
+        // When
+        actor.send(Shutdown).await.unwrap();
+        let rest = candlesticks.collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        assert!(rest.len() < 59);
+        while actor.connected() {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[actix::test]
+    async fn test_failed_symbol_does_not_abort_the_job() {
+        // Given
+        let btc_attempts = Arc::new(AtomicUsize::new(0));
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client.expect_fetch_candlesticks().returning({
+            let btc_attempts = btc_attempts.clone();
+            move |symbol, timeframe, limit, start_time, end_time| {
+                // ETH is always broken, BTC fails only on the first attempt
+                let broken = symbol.base_asset == "ETH" || btc_attempts.fetch_add(1, Ordering::SeqCst) == 0;
+                Box::pin(async move {
+                    match broken {
+                        true => Err(fake_http_error()),
+                        false => fake_candlesticks(symbol, timeframe, limit, start_time, end_time).await,
+                    }
+                })
+            }
+        });
+        let retry_policy = RetryPolicy::new(2, std::time::Duration::from_millis(1), std::time::Duration::from_millis(1));
+        let actor = CandlesticksDownloaderActor::new(10, 2, Arc::new(binance_client), 0)
+            .with_retry_policy(retry_policy)
+            .start();
+
+        // When
+        let JobReceiver { job_id, receiver } = actor.send(btc_and_eth_download()).await.unwrap().unwrap();
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+        let failures = progress.failures();
+
+        assert_eq!(30, result.into_iter().flatten().count());
+        assert_eq!(1, progress.count(&WindowState::Done));
+        assert_eq!(1, failures.len());
+        assert_eq!("ETH", failures[0].0.symbol.base_asset);
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_new_job_is_registered() {
+        // Given
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse
+            .expect_create_job()
+            .withf(|_, windows| windows.len() == 2)
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let actor = CandlesticksDownloaderActor::new(10, 2, DEFAULT_BINANCE_SPOT_CLIENT.clone(), 0)
+            .with_checkpoints(Arc::new(clickhouse))
+            .start();
+
+        // When
+        let JobReceiver { job_id, receiver } = actor.send(btc_and_eth_download()).await.unwrap().unwrap();
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+
+        assert_eq!(60, result.into_iter().flatten().count());
+        assert!(progress.job_id.is_some());
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_resume_job_continues_after_checkpoint() {
+        // Given
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let planned_start = OffsetDateTime::parse("2025-06-29T00:00:00Z", &Rfc3339).unwrap();
+        let resume_from = OffsetDateTime::parse("2025-06-29T18:30:00Z", &Rfc3339).unwrap();
+        let remaining_window = CandlesticksWindow::new(btc_usdt, ThreeMinutes, resume_from, None);
+
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse.expect_create_job().never();
+        clickhouse.expect_fetch_remaining_windows().withf(|job_id| *job_id == 42).returning(move |_| {
+            let remaining_window = remaining_window.clone();
+            Box::pin(async move { Ok(vec![(planned_start, remaining_window)]) })
+        });
+        let actor = CandlesticksDownloaderActor::new(10, 2, DEFAULT_BINANCE_SPOT_CLIENT.clone(), 0)
+            .with_checkpoints(Arc::new(clickhouse))
+            .start();
+
+        // When
+        let JobReceiver { job_id, receiver } = actor.send(ResumeJob { job_id: 42 }).await.unwrap().unwrap();
+        let result = ReceiverStream::new(receiver).collect::<Vec<_>>().await;
+
+        // Then: pages keep the planned start of the window, its checkpoints are keyed by it
+        assert!(result.iter().all(|(window_start, _)| *window_start == planned_start));
+        let candlesticks = result.into_iter().flat_map(|(_, page)| page).collect::<Vec<_>>();
+        let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+
+        assert_eq!(4, candlesticks.len());
+        assert_eq!(resume_from, candlesticks[0].open_time);
+        assert_eq!(42, job_id);
+        assert_eq!(Some(42), progress.job_id);
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_resume_job_requires_checkpoints() {
+        // Given
+        let actor = CandlesticksDownloaderActor::new(10, 2, DEFAULT_BINANCE_SPOT_CLIENT.clone(), 0).start();
+
+        // When
+        let err = actor.send(ResumeJob { job_id: 42 }).await.unwrap().unwrap_err();
+
+        // Then
+        assert_eq!(DownloadHistoryError::CheckpointsAreDisabled, err);
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_windows_are_trimmed_to_listing_dates() {
+        // Given
+        let discoveries = Arc::new(AtomicUsize::new(0));
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client.expect_fetch_candlesticks().returning({
+            let discoveries = discoveries.clone();
+            move |symbol, timeframe, limit, start_time, end_time| {
+                if start_time == Some(OffsetDateTime::UNIX_EPOCH) {
+                    discoveries.fetch_add(1, Ordering::SeqCst);
+                }
+                // SOL has no fixtures, the exchange knows nothing about it
+                let listed = symbol.base_asset != "SOL";
+                Box::pin(async move {
+                    match listed {
+                        true => fake_candlesticks(symbol, timeframe, limit, start_time, end_time).await,
+                        false => Ok((vec![], HEADER_MAP.clone())),
+                    }
+                })
+            }
+        });
+        let actor = CandlesticksDownloaderActor::new(10, 2, Arc::new(binance_client), 0).start();
+
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let sol_usdt = Symbol::from_pool(Binance, "SOL".to_string(), "USDT".to_string());
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let listed_at = OffsetDateTime::parse("2025-06-29T17:12:00Z", &Rfc3339).unwrap();
+
+        let msg = DownloadCandlesticks {
+            symbols: Arc::new(vec![btc_usdt, sol_usdt]),
+            timeframes: vec![(ThreeMinutes, start_date)],
+            end_date: None,
+            filter: Arc::new(|_| true),
+            resume_from: Arc::new(LastOpenTimes::new()),
+        };
+
+        // When
+        let JobReceiver { job_id, receiver } = actor.send(msg.clone()).await.unwrap().unwrap();
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+        let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+
+        let receiver = actor.send(msg).await.unwrap().unwrap().receiver;
+        ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        assert_eq!(30, result.into_iter().flatten().count());
+        assert_eq!(listed_at, progress.windows[0].window.start);
+        assert_eq!(WindowState::Done, progress.windows[0].state);
+        assert_eq!(WindowState::Skipped, progress.windows[1].state);
+        // BTC is served from the cache, SOL is looked up again since it may have been listed meanwhile
+        assert_eq!(3, discoveries.load(Ordering::SeqCst));
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_symbol_is_downloaded_once_it_starts_trading() {
+        // Given: BTC is announced, but has no candlestick during the first job
+        let trading = Arc::new(AtomicBool::new(false));
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client.expect_fetch_candlesticks().returning({
+            let trading = trading.clone();
+            move |symbol, timeframe, limit, start_time, end_time| {
+                let trading = trading.load(Ordering::SeqCst);
+                Box::pin(async move {
+                    match trading {
+                        true => fake_candlesticks(symbol, timeframe, limit, start_time, end_time).await,
+                        false => Ok((vec![], HEADER_MAP.clone())),
+                    }
+                })
+            }
+        });
+        let actor = CandlesticksDownloaderActor::new(10, 2, Arc::new(binance_client), 0).start();
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let window = CandlesticksWindow::new(btc_usdt, ThreeMinutes, start_date, None);
+
+        let JobReceiver { job_id, receiver } = actor.send(BackfillCandlesticks { windows: vec![window.clone()] }).await.unwrap().unwrap();
+        let before_listing = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+        let skipped = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+
+        // When: it starts trading before the next job
+        trading.store(true, Ordering::SeqCst);
+        let receiver = actor.send(BackfillCandlesticks { windows: vec![window] }).await.unwrap().unwrap().receiver;
+        let after_listing = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        assert!(before_listing.is_empty());
+        assert_eq!(WindowState::Skipped, skipped.windows[0].state);
+        assert_eq!(30, after_listing.into_iter().flatten().count());
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_listing_dates_are_loaded_and_saved() {
+        // Given: BTC was discovered by a previous run
+        let listed_at = OffsetDateTime::parse("2025-06-29T17:12:00Z", &Rfc3339).unwrap();
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let discoveries = Arc::new(AtomicUsize::new(0));
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client.expect_fetch_candlesticks().returning({
+            let discoveries = discoveries.clone();
+            move |symbol, timeframe, limit, start_time, end_time| {
+                if start_time == Some(OffsetDateTime::UNIX_EPOCH) {
+                    discoveries.fetch_add(1, Ordering::SeqCst);
+                }
+                Box::pin(fake_candlesticks(symbol, timeframe, limit, start_time, end_time))
+            }
+        });
+
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse.expect_fetch_listing_dates().times(1).returning({
+            let btc_usdt = btc_usdt.clone();
+            move || {
+                let persisted = KnownListingDates::from([((btc_usdt.clone(), ThreeMinutes), listed_at)]);
+                Box::pin(async move { Ok(persisted) })
+            }
+        });
+        clickhouse
+            .expect_save_listing_dates()
+            .withf(move |listing_dates| *listing_dates == KnownListingDates::from([((eth_usdt.clone(), ThreeMinutes), listed_at)]))
+            .times(1)
+            .returning(|_| Box::pin(async { Ok(()) }));
+        let actor = CandlesticksDownloaderActor::new(10, 2, Arc::new(binance_client), 0)
+            .with_listing_dates_store(Arc::new(clickhouse))
+            .start();
+
+        // When
+        let receiver = actor.send(btc_and_eth_download()).await.unwrap().unwrap().receiver;
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then: only ETH is looked up on the exchange
+        assert_eq!(60, result.into_iter().flatten().count());
+        assert_eq!(1, discoveries.load(Ordering::SeqCst));
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_jobs_are_queued() {
+        // Given
+        let actor = CandlesticksDownloaderActor::new(10, 2, one_by_one_binance_client(), 0).start();
+
+        // When
+        let first = actor.send(btc_and_eth_download()).await.unwrap().unwrap();
+        let second = actor.send(btc_and_eth_download()).await.unwrap().unwrap();
+        let third = actor.send(btc_and_eth_download()).await.unwrap().unwrap();
+        let jobs = actor.send(ListJobs).await.unwrap();
+
+        assert!(actor.send(CancelDownload { job_id: Some(third.job_id) }).await.unwrap());
+        let first_result = ReceiverStream::new(first.receiver)
+            .map(|(_, page)| page)
+            .collect::<Vec<Vec<Candlestick>>>()
+            .await;
+        let second_result = ReceiverStream::new(second.receiver)
+            .map(|(_, page)| page)
+            .collect::<Vec<Vec<Candlestick>>>()
+            .await;
+        let third_result = ReceiverStream::new(third.receiver)
+            .map(|(_, page)| page)
+            .collect::<Vec<Vec<Candlestick>>>()
+            .await;
+
+        // Then
+        let statuses = jobs.iter().map(|job| (job.job_id, job.status)).collect::<Vec<_>>();
+        let cancelled = actor.send(GetProgress(third.job_id)).await.unwrap().unwrap();
+
+        assert_eq!(
+            vec![
+                (first.job_id, JobStatus::Running),
+                (second.job_id, JobStatus::Queued),
+                (third.job_id, JobStatus::Queued)
+            ],
+            statuses
+        );
+        assert_eq!(None, jobs[1].details.started_at);
+        assert_eq!(60, first_result.into_iter().flatten().count());
+        assert_eq!(60, second_result.into_iter().flatten().count());
+        assert!(third_result.is_empty());
+        assert_eq!(2, cancelled.count(&WindowState::Cancelled));
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_active_job_can_not_be_resumed_twice() {
+        // Given
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let remaining_window = CandlesticksWindow::new(btc_usdt, ThreeMinutes, start_date, None);
+
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse.expect_fetch_remaining_windows().returning(move |_| {
+            let remaining_window = remaining_window.clone();
+            Box::pin(async move { Ok(vec![(start_date, remaining_window)]) })
+        });
+        let actor = CandlesticksDownloaderActor::new(10, 2, one_by_one_binance_client(), 0)
+            .with_checkpoints(Arc::new(clickhouse))
+            .start();
+
+        // When
+        let resumed = actor.send(ResumeJob { job_id: 42 }).await.unwrap().unwrap();
+        let err = actor.send(ResumeJob { job_id: 42 }).await.unwrap().unwrap_err();
+
+        // Then
+        assert_eq!(DownloadHistoryError::JobIsActive(42), err);
+        assert_eq!(
+            30,
+            ReceiverStream::new(resumed.receiver)
+                .map(|(_, page)| page)
+                .collect::<Vec<Vec<Candlestick>>>()
+                .await
+                .len()
+        );
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_partial_candles_follow_policy() {
+        // Given: the exchange time is within the last fixture candlestick
+        let exchange_time = OffsetDateTime::parse("2025-06-29T18:40:00Z", &Rfc3339).unwrap();
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client
+            .expect_fetch_candlesticks()
+            .returning(move |symbol, timeframe, limit, start_time, end_time| {
+                Box::pin(async move {
+                    let (candlesticks, headers) = fake_candlesticks(symbol, timeframe, limit, start_time, end_time).await?;
+                    let candlesticks = candlesticks
+                        .into_iter()
+                        .map(|candlestick| Candlestick {
+                            is_closed: candlestick.is_closed_at(exchange_time),
+                            ..candlestick
+                        })
+                        .collect();
+                    Ok((candlesticks, headers))
+                })
+            });
+        let binance_client = Arc::new(binance_client);
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let msg = BackfillCandlesticks {
+            windows: vec![CandlesticksWindow::new(btc_usdt, ThreeMinutes, start_date, None)],
+        };
+
+        let skipping = CandlesticksDownloaderActor::new(10, 2, binance_client.clone(), 0).start();
+        let overwriting = CandlesticksDownloaderActor::new(10, 2, binance_client, 0)
+            .with_partial_candle_policy(PartialCandlePolicy::Overwrite)
+            .start();
+
+        // When
+        let skipped = skipping.send(msg.clone()).await.unwrap().unwrap().receiver;
+        let skipped = ReceiverStream::new(skipped).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+        let overwritten = overwriting.send(msg).await.unwrap().unwrap().receiver;
+        let overwritten = ReceiverStream::new(overwritten).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        let skipped = skipped.into_iter().flatten().collect::<Vec<_>>();
+        let overwritten = overwritten.into_iter().flatten().collect::<Vec<_>>();
+
+        assert_eq!(29, skipped.len());
+        assert!(skipped.iter().all(|candlestick| candlestick.is_closed));
+        assert_eq!(30, overwritten.len());
+        assert!(!overwritten[29].is_closed);
+        skipping.send(Shutdown).await.unwrap();
+        overwriting.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_window_is_fetched_in_parallel_shards() {
+        // Given: a single candlestick at the start of every 1000 hours, pages in between are empty
+        let listed_at = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client
+            .expect_fetch_candlesticks()
+            .returning(move |symbol, timeframe, _, start_time, _| {
+                let open_time = start_time.unwrap().max(listed_at);
+                let on_shard_start = (open_time - listed_at).whole_milliseconds() % Duration::hours(1000).whole_milliseconds() == 0;
+                let candlesticks = match on_shard_start {
+                    true => vec![Candlestick {
+                        symbol,
+                        close_time: open_time + timeframe.duration() - Duration::milliseconds(1),
+                        timeframe,
+                        open_time,
+                        open_price: 1.0,
+                        close_price: 1.0,
+                        low_price: 1.0,
+                        high_price: 1.0,
+                        volume: 1.0,
+                        is_closed: true,
+                    }],
+                    false => vec![],
+                };
+                Box::pin(async move { Ok((candlesticks, HEADER_MAP.clone())) })
+            });
+        let actor = CandlesticksDownloaderActor::new(10, 3, Arc::new(binance_client), 0).with_shard_pages(1).start();
+
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let end_date = listed_at + Duration::hours(2999);
+        let msg = BackfillCandlesticks {
+            windows: vec![CandlesticksWindow::new(btc_usdt, OneHour, listed_at, Some(end_date))],
+        };
+
+        // When
+        let JobReceiver { job_id, receiver } = actor.send(msg).await.unwrap().unwrap();
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then: paging alone would stop at the first empty page
+        let open_times = result.into_iter().flatten().map(|candlestick| candlestick.open_time).collect::<Vec<_>>();
+        let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+
+        assert_eq!(
+            vec![listed_at, listed_at + Duration::hours(1000), listed_at + Duration::hours(2000)],
+            open_times
+        );
+        assert_eq!(WindowState::Done, progress.windows[0].state);
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_plan_uses_known_listing_dates() {
+        // Given: no fetch is expected while planning
+        let actor = CandlesticksDownloaderActor::new(10, 2, Arc::new(MockBinanceSpotClient::new()), 0).start();
+        let download = btc_and_eth_download();
+
+        // When
+        let plan = actor.send(PlanDownload { windows: download.windows() }).await.unwrap();
+
+        // Then: listing dates are unknown, both symbols are reported instead of being planned from the start date
+        assert_eq!(2, plan.listing_lookups);
+        assert_eq!(2, plan.unknown_listing_symbols().len());
+        assert!(plan.symbols().is_empty());
+        assert_eq!(2, plan.requests);
+        assert_eq!(plan.requests as u64 * 2, plan.weight);
+        actor.send(Shutdown).await.unwrap();
+
+        // Given: a previous job discovered the listing dates
+        let actor = CandlesticksDownloaderActor::new(10, 2, DEFAULT_BINANCE_SPOT_CLIENT.clone(), 0).start();
+        let receiver = actor.send(download.clone()).await.unwrap().unwrap().receiver;
+        ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // When
+        let plan = actor.send(PlanDownload { windows: download.windows() }).await.unwrap();
+
+        // Then
+        let listed_at = OffsetDateTime::parse("2025-06-29T17:12:00Z", &Rfc3339).unwrap();
+        assert_eq!(0, plan.listing_lookups);
+        assert!(plan.unknown_listing.is_empty());
+        assert!(plan.windows.iter().all(|window| window.window.start == listed_at));
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_request_rate_drops_after_a_used_weight_backoff() {
+        // Given: pages take 20ms, the used weight crosses the backoff threshold after 40 pages
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let requested_at = Arc::new(Mutex::new(Vec::new()));
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client.expect_fetch_candlesticks().returning({
+            let requested_at = requested_at.clone();
+            move |symbol, timeframe, limit, start_time, _| {
+                // listing lookups ask for a single candlestick and are not paged
+                let listing_lookup = limit == 1;
+                let pages = {
+                    let mut requested_at = requested_at.lock().unwrap();
+                    if !listing_lookup {
+                        requested_at.push(std::time::Instant::now());
+                    }
+                    requested_at.len()
+                };
+                let start_time = start_time.unwrap().max(start_date);
+                // the next page starts right after the close time of the previous candlestick
+                let truncated = start_time.replace_nanosecond(0).unwrap();
+                let open_time = if truncated == start_time {
+                    start_time
+                } else {
+                    truncated + Duration::seconds(1)
+                };
+                Box::pin(async move {
+                    if !listing_lookup {
+                        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    }
+                    let used_weight = if pages <= 40 { "10" } else { "5000" };
+                    let mut headers = HEADER_MAP.clone();
+                    headers.insert(BINANCE_HEADER_USED_WEIGHT_1M, HeaderValue::from_static(used_weight));
+                    let candlestick = Candlestick {
+                        symbol,
+                        timeframe,
+                        open_time,
+                        close_time: open_time + Duration::minutes(3) - Duration::milliseconds(1),
+                        open_price: 1.0,
+                        close_price: 1.0,
+                        low_price: 1.0,
+                        high_price: 1.0,
+                        volume: 1.0,
+                        is_closed: true,
+                    };
+                    Ok((vec![candlestick], headers))
+                })
+            }
+        });
+        let actor = CandlesticksDownloaderActor::new(100, 8, Arc::new(binance_client), 1).start();
+        let symbols = (0..8)
+            .map(|index| Symbol::from_pool(Binance, format!("S{}", index), "USDT".to_string()))
+            .collect();
+        let download = DownloadCandlesticks {
+            symbols: Arc::new(symbols),
+            timeframes: vec![(ThreeMinutes, start_date)],
+            end_date: Some(start_date + Duration::days(1)),
+            filter: Arc::new(|_| true),
+            resume_from: Arc::new(LastOpenTimes::new()),
+        };
+
+        // When
+        let JobReceiver { receiver, .. } = actor.send(download).await.unwrap().unwrap();
+        let pages = ReceiverStream::new(receiver).collect::<Vec<_>>();
+        let _ = tokio::time::timeout(std::time::Duration::from_millis(700), pages).await;
+        actor.send(Shutdown).await.unwrap();
+
+        // Then: 8 pages are in flight at first, a single one once the controller backed off
+        let requested_at = requested_at.lock().unwrap().clone();
+        let early_rate = 40.0 / (requested_at[39] - requested_at[0]).as_secs_f64();
+        let last = *requested_at.last().unwrap();
+        let late_window = std::time::Duration::from_millis(300);
+        let late_requests = requested_at.iter().filter(|requested| last - **requested <= late_window).count();
+        let late_rate = late_requests as f64 / late_window.as_secs_f64();
+        assert!(
+            late_rate * 3.0 < early_rate,
+            "{} requests/s after the backoff, {} before",
+            late_rate,
+            early_rate
+        );
+    }
+
+    #[actix::test]
+    async fn test_crashed_job_is_restarted_from_its_progress() {
+        // Given: the 10th page request panics
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client.expect_fetch_candlesticks().returning({
+            let calls = calls.clone();
+            move |symbol, timeframe, _, start_time, end_time| {
+                let crash = calls.fetch_add(1, Ordering::SeqCst) == 9;
+                Box::pin(async move {
+                    if crash {
+                        panic!("klines page exploded");
+                    }
+                    fake_candlesticks(symbol, timeframe, 1, start_time, end_time).await
+                })
+            }
+        });
+        let restart_policy = RestartPolicy::new(1, std::time::Duration::from_millis(1), std::time::Duration::from_millis(1));
+        let actor = CandlesticksDownloaderActor::new(10, 2, Arc::new(binance_client), 0)
+            .with_restart_policy(restart_policy)
+            .start();
+        let mut events = actor.send(GetLifecycleReceiver).await.unwrap();
+
+        // When
+        let JobReceiver { job_id, receiver } = actor.send(btc_and_eth_download()).await.unwrap().unwrap();
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then: the restart continues after the candlesticks that were already sent, nothing is missing or repeated
+        let candlesticks = result.into_iter().flatten().collect::<Vec<_>>();
+        let distinct = candlesticks
+            .iter()
+            .map(|candlestick| (candlestick.symbol.clone(), candlestick.open_time))
+            .collect::<HashSet<_>>();
+        let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+
+        assert_eq!(60, candlesticks.len());
+        assert_eq!(60, distinct.len());
+        assert_eq!(2, progress.count(&WindowState::Done));
+        assert!(matches!(events.recv().await.unwrap(), LifecycleEvent::Crashed { reason, .. } if reason == "klines page exploded"));
+        assert!(matches!(events.recv().await.unwrap(), LifecycleEvent::Restarting { restart: 1, .. }));
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_restarts_are_budgeted_per_job() {
+        // Given: the first page of every symbol panics, a job may restart once
+        let crashed_symbols = Arc::new(Mutex::new(HashSet::new()));
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client.expect_fetch_candlesticks().returning({
+            let crashed_symbols = crashed_symbols.clone();
+            move |symbol, timeframe, _, start_time, end_time| {
+                let crash = crashed_symbols.lock().unwrap().insert(symbol.base_asset.clone());
+                Box::pin(async move {
+                    if crash {
+                        panic!("klines page exploded");
+                    }
+                    fake_candlesticks(symbol, timeframe, 1, start_time, end_time).await
+                })
+            }
+        });
+        let restart_policy = RestartPolicy::new(1, std::time::Duration::from_millis(100), std::time::Duration::from_millis(100));
+        let actor = CandlesticksDownloaderActor::new(10, 2, Arc::new(binance_client), 0)
+            .with_max_concurrent_jobs(2)
+            .with_restart_policy(restart_policy)
+            .start();
+        let mut events = actor.send(GetLifecycleReceiver).await.unwrap();
+
+        // When: both jobs crash before either of them is restarted
+        let btc = DownloadCandlesticks {
+            filter: Arc::new(|symbol| symbol.base_asset == "BTC"),
+            ..btc_and_eth_download()
+        };
+        let eth = DownloadCandlesticks {
+            filter: Arc::new(|symbol| symbol.base_asset == "ETH"),
+            ..btc_and_eth_download()
+        };
+        let btc = actor.send(btc).await.unwrap().unwrap();
+        let eth = actor.send(eth).await.unwrap().unwrap();
+        let btc_candlesticks = ReceiverStream::new(btc.receiver)
+            .map(|(_, page)| page.len())
+            .fold(0, |sum, count| sum + count)
+            .await;
+        let eth_candlesticks = ReceiverStream::new(eth.receiver)
+            .map(|(_, page)| page.len())
+            .fold(0, |sum, count| sum + count)
+            .await;
+
+        // Then: the crash of one job doesn't use the restart of the other
+        assert_eq!(30, btc_candlesticks);
+        assert_eq!(30, eth_candlesticks);
+        for job_id in [btc.job_id, eth.job_id] {
+            let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+            assert_eq!(1, progress.count(&WindowState::Done));
+        }
+        let mut restarts = 0;
+        while let Ok(event) = events.try_recv() {
+            assert!(!matches!(event, LifecycleEvent::GaveUp { .. }));
+            restarts += matches!(event, LifecycleEvent::Restarting { restart: 1, .. }) as usize;
+        }
+        assert_eq!(2, restarts);
+        actor.send(Shutdown).await.unwrap();
+    }
+
+    #[actix::test]
+    async fn test_crashing_job_fails_once_restarts_are_exhausted() {
+        // Given
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client
+            .expect_fetch_candlesticks()
+            .returning(|_, _, _, _, _| Box::pin(async { panic!("klines page exploded") }));
+        let actor = CandlesticksDownloaderActor::new(10, 2, Arc::new(binance_client), 0)
+            .with_restart_policy(RestartPolicy::never())
+            .start();
+        let mut events = actor.send(GetLifecycleReceiver).await.unwrap();
+
+        // When
+        let JobReceiver { job_id, receiver } = actor.send(btc_and_eth_download()).await.unwrap().unwrap();
+        let result = ReceiverStream::new(receiver).map(|(_, page)| page).collect::<Vec<Vec<Candlestick>>>().await;
+
+        // Then
+        let progress = actor.send(GetProgress(job_id)).await.unwrap().unwrap();
+
+        assert!(result.is_empty());
+        assert_eq!(2, progress.failures().len());
+        assert!(matches!(events.recv().await.unwrap(), LifecycleEvent::Crashed { .. }));
+        assert!(matches!(events.recv().await.unwrap(), LifecycleEvent::GaveUp { restarts: 0, .. }));
+        actor.send(Shutdown).await.unwrap();
+    }
 }
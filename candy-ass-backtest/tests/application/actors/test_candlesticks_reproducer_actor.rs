@@ -4,7 +4,10 @@ mod tests {
     use candy_ass_backtest::application::history_reproducer::candlesticks_reproducer_actor::CandlesticksReproducerActor;
     use candy_ass_backtest::application::history_reproducer::candlesticks_reproducer_actor::commands::ProduceCandlesticks;
     use candy_ass_backtest::application::history_reproducer::candlesticks_reproducer_actor::errors::ReproduceHistoryError;
+    use candy_ass_backtest::application::history_reproducer::candlesticks_reproducer_actor::queries::ListJobs;
+    use candy_ass_backtest::application::job_queue::JobStatus;
     use candy_ass_backtest::integrations::clickhouse::ClickhouseRepositoryError;
+    use candy_ass_backtest::integrations::clickhouse::candlesticks_repository::CandlesticksFilter;
     use candy_ass_backtest::mocks::mock_clickhouse::MockClickhouse;
     use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
     use candy_ass_core::domain::symbol::Symbol;
@@ -22,13 +25,16 @@ mod tests {
         let candlesticks = mock_candlesticks(symbol).await.expect("expected mock candlesticks");
 
         let mut clickhouse = MockClickhouse::new();
-        clickhouse.expect_fetch_candlesticks_between().returning(move |_, _, _| {
-            let candlesticks = candlesticks.clone();
-            Box::pin(async move { Ok(candlesticks) })
-        });
+        clickhouse
+            .expect_fetch_candlesticks_between()
+            .withf(|_, _, _, include_unclosed, _| !include_unclosed)
+            .returning(move |_, _, _, _, _| {
+                let candlesticks = candlesticks.clone();
+                Box::pin(async move { Ok(candlesticks) })
+            });
 
-        // Given
-        let actor = CandlesticksReproducerActor::new(2, Arc::new(clickhouse)).start();
+        // Given: a single slot buffer keeps the first job running until it is consumed
+        let actor = CandlesticksReproducerActor::new(1, Arc::new(clickhouse)).start();
 
         // When
         let command = ProduceCandlesticks {
@@ -36,17 +42,30 @@ mod tests {
             start_date: OffsetDateTime::now_utc() - Duration::days(1),
             end_date: OffsetDateTime::now_utc(),
             step: Duration::days(1),
+            include_unclosed: false,
+            filter: CandlesticksFilter::default(),
         };
 
-        let candlesticks_reproducer_actor = actor.send(command.clone()).await.unwrap().unwrap();
-        let err = actor.send(command.clone()).await.unwrap().unwrap_err();
-        assert_eq!(err, ReproduceHistoryError::ActorIsBusy);
+        let first = actor.send(command.clone()).await.unwrap().unwrap();
+        let second = actor.send(command.clone()).await.unwrap().unwrap();
+        let jobs = actor.send(ListJobs).await.unwrap();
 
-        let result = ReceiverStream::new(candlesticks_reproducer_actor)
+        let result = ReceiverStream::new(first.receiver)
             .flat_map(|(_date_time, candlesticks)| futures::stream::iter(candlesticks))
             .collect::<Vec<_>>()
             .await;
+        let queued_result = ReceiverStream::new(second.receiver)
+            .flat_map(|(_date_time, candlesticks)| futures::stream::iter(candlesticks))
+            .collect::<Vec<_>>()
+            .await;
+
+        // Then
+        let statuses = jobs.iter().map(|job| job.status).collect::<Vec<_>>();
+
+        assert_ne!(first.job_id, second.job_id);
+        assert_eq!(vec![JobStatus::Running, JobStatus::Queued], statuses);
         assert_eq!(60, result.len());
+        assert_eq!(60, queued_result.len());
     }
 
     #[actix::test]
@@ -54,7 +73,7 @@ mod tests {
         let mut clickhouse = MockClickhouse::new();
         clickhouse
             .expect_fetch_candlesticks_between()
-            .returning(move |_, _, _| Box::pin(async move { Err(ClickhouseRepositoryError::UnexpectedResult(RowNotFound)) }));
+            .returning(move |_, _, _, _, _| Box::pin(async move { Err(ClickhouseRepositoryError::UnexpectedResult(RowNotFound)) }));
 
         // Given
         let actor = CandlesticksReproducerActor::new(2, Arc::new(clickhouse)).start();
@@ -65,14 +84,36 @@ mod tests {
             start_date: OffsetDateTime::now_utc() - Duration::days(1),
             end_date: OffsetDateTime::now_utc(),
             step: Duration::days(1),
+            include_unclosed: false,
+            filter: CandlesticksFilter::default(),
         };
 
-        let candlesticks_reproducer_actor = actor.send(command.clone()).await.unwrap().unwrap();
+        let job = actor.send(command.clone()).await.unwrap().unwrap();
 
-        let result = ReceiverStream::new(candlesticks_reproducer_actor)
+        let result = ReceiverStream::new(job.receiver)
             .flat_map(|(_date_time, candlesticks)| futures::stream::iter(candlesticks))
             .collect::<Vec<_>>()
             .await;
         assert_eq!(0, result.len());
     }
+
+    #[actix::test]
+    async fn test_reproducer_rejects_empty_step() {
+        // Given
+        let actor = CandlesticksReproducerActor::new(2, Arc::new(MockClickhouse::new())).start();
+
+        // When
+        let command = ProduceCandlesticks {
+            timeframes: vec![OneDay],
+            start_date: OffsetDateTime::now_utc() - Duration::days(1),
+            end_date: OffsetDateTime::now_utc(),
+            step: Duration::ZERO,
+            include_unclosed: false,
+            filter: CandlesticksFilter::default(),
+        };
+        let err = actor.send(command).await.unwrap().unwrap_err();
+
+        // Then
+        assert_eq!(ReproduceHistoryError::InvalidStep(Duration::ZERO), err);
+    }
 }
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use candy_ass_backtest::application::history_downloader::{ApplicationBuilder, DownloadMode, DownloadRequest};
+    use candy_ass_backtest::application::history_reproducer;
+    use candy_ass_backtest::integrations::clickhouse::candlesticks_repository::CandlesticksFilter;
+    use candy_ass_backtest::mocks::mock_clickhouse::MockClickhouse;
+    use candy_ass_core::domain::candlestick::Candlestick;
+    use candy_ass_core::domain::timeframe::Timeframe::ThreeMinutes;
+    use candy_ass_core::mocks::mock_binance_spot::default::DEFAULT_BINANCE_SPOT_CLIENT;
+    use futures_util::StreamExt;
+    use std::sync::{Arc, Mutex};
+    use time::OffsetDateTime;
+    use time::format_description::well_known::Rfc3339;
+
+    #[actix::test]
+    async fn test_pipeline_runs_on_injected_dependencies() {
+        // Given
+        let stored = Arc::new(Mutex::new(Vec::<Candlestick>::new()));
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse.expect_init().returning(|| Box::pin(async { Ok(()) }));
+        clickhouse.expect_bulk_insert_candlesticks().returning({
+            let stored = stored.clone();
+            move |chunk| {
+                stored.lock().unwrap().extend(chunk.into_iter().flatten());
+                Box::pin(async { Ok(()) })
+            }
+        });
+        let clickhouse = Arc::new(clickhouse);
+        let application = ApplicationBuilder::new(10, 2, clickhouse.clone(), clickhouse)
+            .with_exchange_info_api(DEFAULT_BINANCE_SPOT_CLIENT.clone())
+            .with_klines_api(DEFAULT_BINANCE_SPOT_CLIENT.clone())
+            .build();
+
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let request = DownloadRequest {
+            timeframes: vec![(ThreeMinutes, start_date)],
+            end_date: None,
+            filter: Arc::new(|_| true),
+            mode: DownloadMode::Full,
+        };
+
+        // When
+        let report = application.start_pipeline(request).await.unwrap();
+
+        // Then
+        assert_eq!(60, stored.lock().unwrap().len());
+        assert_eq!(60, report.total_rows_inserted());
+        assert_eq!(2, report.symbols_processed);
+        assert!(report.is_complete());
+    }
+
+    #[actix::test]
+    async fn test_application_runs_pipelines_until_shutdown() {
+        // Given
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse.expect_init().returning(|| Box::pin(async { Ok(()) }));
+        clickhouse.expect_bulk_insert_candlesticks().returning(|_| Box::pin(async { Ok(()) }));
+        let clickhouse = Arc::new(clickhouse);
+        let application = ApplicationBuilder::new(10, 2, clickhouse.clone(), clickhouse)
+            .with_exchange_info_api(DEFAULT_BINANCE_SPOT_CLIENT.clone())
+            .with_klines_api(DEFAULT_BINANCE_SPOT_CLIENT.clone())
+            .build();
+
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let request = DownloadRequest {
+            timeframes: vec![(ThreeMinutes, start_date)],
+            end_date: None,
+            filter: Arc::new(|_| true),
+            mode: DownloadMode::Full,
+        };
+
+        // When: the actors outlive the first pipeline
+        let first = application.start_pipeline(request.clone()).await.unwrap();
+        let second = application.start_pipeline(request).await.unwrap();
+        application.shutdown().await;
+
+        // Then
+        assert_eq!(60, first.total_rows_inserted());
+        assert_eq!(60, second.total_rows_inserted());
+        assert_ne!(first.job_id, second.job_id);
+    }
+
+    #[actix::test]
+    async fn test_reproducer_runs_on_injected_read_service() {
+        // Given
+        let filter = CandlesticksFilter::default()
+            .with_base_assets(vec!["BTC".to_string(), "ETH".to_string()])
+            .with_quote_assets(vec!["USDT".to_string()]);
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse
+            .expect_fetch_candlesticks_between()
+            .withf({
+                let filter = filter.clone();
+                move |_, _, _, _, requested| *requested == filter
+            })
+            .returning(|_, _, _, _, _| Box::pin(async { Ok(vec![]) }));
+        let application = history_reproducer::ApplicationBuilder::new(2, Arc::new(clickhouse)).build();
+
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+
+        // When: the filter is handed to the store instead of being applied to the replayed candlesticks
+        let pipeline = application
+            .start_pipeline(vec![ThreeMinutes], start_date, start_date + time::Duration::days(3), filter)
+            .await;
+        let candlesticks = pipeline.flat_map(|(_, candlesticks)| futures::stream::iter(candlesticks)).count().await;
+
+        // Then
+        assert_eq!(0, candlesticks);
+    }
+}
@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+    use candy_ass_backtest::application::history_reproducer::candlesticks_reproducer_actor::commands::ProduceCandlesticks;
+    use candy_ass_backtest::application::history_reproducer::candlesticks_reproducer_actor::errors::ReproduceHistoryError;
+    use candy_ass_backtest::application::streams::{DownloadOptions, download_candlesticks, replay_candlesticks};
+    use candy_ass_backtest::integrations::clickhouse::ClickhouseRepositoryError;
+    use candy_ass_backtest::integrations::clickhouse::candlesticks_repository::CandlesticksFilter;
+    use candy_ass_backtest::mocks::mock_clickhouse::MockClickhouse;
+    use candy_ass_core::domain::candlestick::Candlestick;
+    use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::symbol::Symbol;
+    use candy_ass_core::domain::timeframe::Timeframe::{OneDay, ThreeMinutes};
+    use candy_ass_core::mocks::mock_binance_spot::default::DEFAULT_BINANCE_SPOT_CLIENT;
+    use clickhouse::error::Error::RowNotFound;
+    use futures_util::{StreamExt, TryStreamExt};
+    use std::sync::Arc;
+    use time::format_description::well_known::Rfc3339;
+    use time::{Duration, OffsetDateTime};
+
+    #[tokio::test]
+    async fn test_download_without_actix() {
+        // Given
+        let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let windows = vec![
+            CandlesticksWindow::new(btc_usdt, ThreeMinutes, start_date, None),
+            CandlesticksWindow::new(eth_usdt, ThreeMinutes, start_date, None),
+        ];
+        let options = DownloadOptions {
+            rate_limit: 0,
+            ..DownloadOptions::default()
+        };
+
+        // When
+        let pages = download_candlesticks(DEFAULT_BINANCE_SPOT_CLIENT.clone(), windows, options)
+            .try_collect::<Vec<Vec<Candlestick>>>()
+            .await
+            .unwrap();
+
+        // Then
+        let candlesticks = pages.into_iter().flatten().collect::<Vec<_>>();
+        assert_eq!(60, candlesticks.len());
+        assert!(candlesticks.iter().all(|candlestick| candlestick.is_closed));
+    }
+
+    #[tokio::test]
+    async fn test_replay_without_actix() {
+        // Given: a candlestick per day for two days, the third day fails
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse.expect_fetch_candlesticks_between().returning(move |_, from, _, _, _| {
+            let btc_usdt = Symbol::from_pool(Binance, "BTC".to_string(), "USDT".to_string());
+            let day = (from - start_date).whole_days();
+            Box::pin(async move {
+                match day {
+                    0 | 1 => Ok(vec![Candlestick {
+                        symbol: btc_usdt,
+                        timeframe: OneDay,
+                        open_time: from,
+                        close_time: from + Duration::days(1) - Duration::milliseconds(1),
+                        open_price: 1.0,
+                        close_price: 1.0,
+                        low_price: 1.0,
+                        high_price: 1.0,
+                        volume: 1.0,
+                        is_closed: true,
+                    }]),
+                    2 => Err(ClickhouseRepositoryError::UnexpectedResult(RowNotFound)),
+                    _ => Ok(vec![]),
+                }
+            })
+        });
+        let request = ProduceCandlesticks {
+            timeframes: vec![OneDay],
+            start_date,
+            end_date: start_date + Duration::days(10),
+            step: Duration::days(1),
+            include_unclosed: false,
+            filter: CandlesticksFilter::default(),
+        };
+
+        // When
+        let slices = replay_candlesticks(Arc::new(clickhouse), request.clone()).unwrap().collect::<Vec<_>>().await;
+
+        // Then: the failed day is reported, the empty days are skipped
+        assert_eq!(3, slices.len());
+        assert_eq!(start_date + Duration::days(1), slices[1].as_ref().unwrap().0);
+        assert!(slices[2].is_err());
+
+        let invalid = ProduceCandlesticks {
+            step: Duration::ZERO,
+            ..request
+        };
+        let result = replay_candlesticks(Arc::new(MockClickhouse::new()), invalid);
+        assert_eq!(Some(ReproduceHistoryError::InvalidStep(Duration::ZERO)), result.err());
+    }
+
+    #[tokio::test]
+    async fn test_filtered_replay_skips_empty_slices() {
+        // Given: the filtered symbol has no candlestick on the first day
+        let start_date = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let mut clickhouse = MockClickhouse::new();
+        clickhouse.expect_fetch_candlesticks_between().times(4).returning(move |_, from, _, _, filter| {
+            let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+            let day = (from - start_date).whole_days();
+            Box::pin(async move {
+                match day {
+                    1 | 3 if filter.matches(&eth_usdt) => Ok(vec![Candlestick {
+                        symbol: eth_usdt,
+                        timeframe: OneDay,
+                        open_time: from,
+                        close_time: from + Duration::days(1) - Duration::milliseconds(1),
+                        open_price: 1.0,
+                        close_price: 1.0,
+                        low_price: 1.0,
+                        high_price: 1.0,
+                        volume: 1.0,
+                        is_closed: true,
+                    }]),
+                    _ => Ok(vec![]),
+                }
+            })
+        });
+        let request = ProduceCandlesticks {
+            timeframes: vec![OneDay],
+            start_date,
+            end_date: start_date + Duration::days(4),
+            step: Duration::days(1),
+            include_unclosed: false,
+            filter: CandlesticksFilter::default().with_symbols(vec![Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string())]),
+        };
+
+        // When
+        let slices = replay_candlesticks(Arc::new(clickhouse), request)
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        // Then: every day up to the end date is read, only the non empty ones are replayed
+        let starts = slices.iter().map(|(slice_start, _)| *slice_start).collect::<Vec<_>>();
+        assert_eq!(vec![start_date + Duration::days(1), start_date + Duration::days(3)], starts);
+    }
+}
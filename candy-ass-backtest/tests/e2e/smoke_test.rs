@@ -1,5 +1,7 @@
+use candy_ass_backtest::application::history_downloader::{DownloadMode, DownloadRequest};
 use candy_ass_backtest::application::{history_downloader, history_reproducer};
 use candy_ass_backtest::config::AppConfig;
+use candy_ass_backtest::integrations::clickhouse::candlesticks_repository::CandlesticksFilter;
 use candy_ass_backtest::mocks::mock_docker_clickhouse::setup_clickhouse_container;
 use candy_ass_core::domain::symbol::SymbolFilterFn;
 use candy_ass_core::domain::timeframe::Timeframe::OneHour;
@@ -28,7 +30,15 @@ async fn smoke_test() {
     let filter: SymbolFilterFn = Arc::new(|symbol| symbol.quote_asset == "USDT" && (symbol.base_asset == "BTC" || symbol.base_asset == "ETH"));
 
     // Run downloader
-    downloader_app.start_pipeline(OneHour, start_date, filter).await;
+    let request = DownloadRequest {
+        timeframes: vec![(OneHour, start_date)],
+        end_date: None,
+        filter,
+        mode: DownloadMode::Full,
+    };
+    let report = downloader_app.start_pipeline(request).await.expect("Download pipeline failed");
+    assert_eq!(2, report.symbols_processed);
+    assert!(report.is_complete());
     downloader_app.run_optimization().await;
 
     // Setup reproducer
@@ -37,7 +47,7 @@ async fn smoke_test() {
 
     // Run reproducer
     let stream = reproducer_app
-        .start_pipeline(vec![OneHour], start_date, now)
+        .start_pipeline(vec![OneHour], start_date, now, CandlesticksFilter::default())
         .await
         .flat_map(|(_date_time, candlesticks)| futures::stream::iter(candlesticks))
         .collect::<Vec<_>>()
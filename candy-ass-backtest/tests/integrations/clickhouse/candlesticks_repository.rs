@@ -1,10 +1,17 @@
 #[cfg(test)]
 mod integration_tests {
-    use candy_ass_backtest::config::{AppConfig, ClickhouseConfig};
-    use candy_ass_backtest::integrations::clickhouse::candlesticks_repository::{CandlesticksReadService, CandlesticksRepository, CandlesticksWriteService};
+    use candy_ass_backtest::config::{AppConfig, ClickhouseConfig, StorageLayout};
+    use candy_ass_backtest::integrations::clickhouse::candlesticks_repository::{
+        CandlesticksFilter, CandlesticksReadService, CandlesticksRepository, CandlesticksWriteService, DownloadCheckpointsService, KnownListingDates,
+        ListingDatesService, SchemaMigrationsService, WindowCheckpoints,
+    };
+    use candy_ass_backtest::integrations::clickhouse::migrations::{MIGRATIONS, MigrationState};
     use candy_ass_backtest::integrations::clickhouse_client;
     use candy_ass_backtest::mocks::mock_docker_clickhouse::setup_clickhouse_container;
-    use candy_ass_core::domain::timeframe::Timeframe::OneDay;
+    use candy_ass_core::domain::candlesticks_window::CandlesticksWindow;
+    use candy_ass_core::domain::exchange_type::ExchangeType::Binance;
+    use candy_ass_core::domain::symbol::Symbol;
+    use candy_ass_core::domain::timeframe::Timeframe::{OneDay, OneHour};
     use candy_ass_core::mocks::fixtures::BTC_USDT_CANDLESTICK;
     use testcontainers::{ContainerAsync, GenericImage};
     use time::format_description::well_known::Rfc3339;
@@ -25,15 +32,161 @@ mod integration_tests {
 
         // flow
         let _ = repository.init().await;
+        let statuses = repository.migration_status().await.unwrap();
+        assert_eq!(MIGRATIONS.len(), statuses.len());
+        assert!(statuses.iter().all(|status| matches!(status.state, MigrationState::Applied(_))));
+        assert!(repository.migrate().await.unwrap().is_empty());
         let _ = repository.bulk_insert_candlesticks(vec![vec![BTC_USDT_CANDLESTICK.clone()]]).await;
 
+        let mut later_candlestick = BTC_USDT_CANDLESTICK.clone();
+        later_candlestick.open_time = start_date + Duration::days(3);
+        later_candlestick.close_time = start_date + Duration::days(4);
+        let _ = repository.bulk_insert_candlesticks(vec![vec![later_candlestick]]).await;
+
         let result = repository
-            .fetch_candlesticks_between(vec![OneDay], start_date, start_date + Duration::days(1))
+            .fetch_candlesticks_between(vec![OneDay], start_date, start_date + Duration::days(1), false, CandlesticksFilter::default())
             .await
             .unwrap();
 
         assert_eq!(1, result.len());
         assert_eq!(100_000.0, result[0].open_price);
         assert_eq!(101_000.0, result[0].close_price);
+
+        let other_symbols = CandlesticksFilter::default().with_base_assets(vec!["ETH".to_string()]);
+        let filtered = repository
+            .fetch_candlesticks_between(vec![OneDay], start_date, start_date + Duration::days(1), false, other_symbols)
+            .await
+            .unwrap();
+        let same_symbol = CandlesticksFilter::default()
+            .with_exchanges(vec![Binance])
+            .with_quote_assets(vec!["USDT".to_string()])
+            .with_symbols(vec![BTC_USDT_CANDLESTICK.symbol.clone()]);
+        let matching = repository
+            .fetch_candlesticks_between(vec![OneDay], start_date, start_date + Duration::days(1), false, same_symbol)
+            .await
+            .unwrap();
+        assert!(filtered.is_empty());
+        assert_eq!(1, matching.len());
+
+        let mut open_candlestick = BTC_USDT_CANDLESTICK.clone();
+        open_candlestick.timeframe = OneHour;
+        open_candlestick.is_closed = false;
+        let _ = repository.bulk_insert_candlesticks(vec![vec![open_candlestick]]).await;
+
+        let closed_only = repository
+            .fetch_candlesticks_between(vec![OneHour], start_date, start_date + Duration::days(1), false, CandlesticksFilter::default())
+            .await
+            .unwrap();
+        let with_unclosed = repository
+            .fetch_candlesticks_between(vec![OneHour], start_date, start_date + Duration::days(1), true, CandlesticksFilter::default())
+            .await
+            .unwrap();
+        assert!(closed_only.is_empty());
+        assert_eq!(1, with_unclosed.len());
+        assert!(!with_unclosed[0].is_closed);
+
+        let last_open_times = repository.fetch_last_open_times(vec![OneDay]).await.unwrap();
+        let key = (BTC_USDT_CANDLESTICK.symbol.clone(), OneDay);
+        assert_eq!(Some(&(start_date + Duration::days(3))), last_open_times.get(&key));
+
+        let eth_usdt = Symbol::from_pool(Binance, "ETH".to_string(), "USDT".to_string());
+        let gap_symbols = vec![BTC_USDT_CANDLESTICK.symbol.clone(), eth_usdt.clone()];
+        let gaps = repository
+            .fetch_gaps(gap_symbols, OneDay, start_date, start_date + Duration::days(7))
+            .await
+            .unwrap();
+        assert_eq!(3, gaps.len());
+        assert_eq!(start_date + Duration::days(1), gaps[0].start);
+        assert_eq!(Some(start_date + Duration::days(2)), gaps[0].end);
+        assert_eq!(start_date + Duration::days(4), gaps[1].start);
+        assert_eq!(Some(start_date + Duration::days(7)), gaps[1].end);
+        assert_eq!(eth_usdt, gaps[2].symbol);
+        assert_eq!(start_date, gaps[2].start);
+
+        let symbol = BTC_USDT_CANDLESTICK.symbol.clone();
+        let job_window = CandlesticksWindow::new(symbol.clone(), OneDay, start_date, Some(start_date + Duration::days(9)));
+        repository.create_job(7, vec![job_window]).await.unwrap();
+        let checkpoints = WindowCheckpoints::from([((symbol.clone(), OneDay, start_date), start_date + Duration::days(3))]);
+        repository.commit_checkpoints(7, checkpoints).await.unwrap();
+
+        let remaining_windows = repository.fetch_remaining_windows(7).await.unwrap();
+        assert_eq!(1, remaining_windows.len());
+        assert_eq!(start_date, remaining_windows[0].0);
+        assert_eq!(start_date + Duration::days(4), remaining_windows[0].1.start);
+        assert_eq!(Some(start_date + Duration::days(9)), remaining_windows[0].1.end);
+
+        // two gaps of the same pair, only the later one is finished
+        let first_gap = CandlesticksWindow::new(symbol.clone(), OneDay, start_date, Some(start_date + Duration::days(2)));
+        let second_gap = CandlesticksWindow::new(symbol.clone(), OneDay, start_date + Duration::days(5), Some(start_date + Duration::days(6)));
+        repository.create_job(8, vec![first_gap, second_gap]).await.unwrap();
+        let checkpoints = WindowCheckpoints::from([
+            ((symbol.clone(), OneDay, start_date), start_date),
+            ((symbol.clone(), OneDay, start_date + Duration::days(5)), start_date + Duration::days(6)),
+        ]);
+        repository.commit_checkpoints(8, checkpoints).await.unwrap();
+
+        let remaining_windows = repository.fetch_remaining_windows(8).await.unwrap();
+        assert_eq!(1, remaining_windows.len());
+        assert_eq!(start_date, remaining_windows[0].0);
+        assert_eq!(start_date + Duration::days(1), remaining_windows[0].1.start);
+        assert_eq!(Some(start_date + Duration::days(2)), remaining_windows[0].1.end);
+
+        let listing_dates = KnownListingDates::from([((symbol.clone(), OneDay), start_date)]);
+        repository.save_listing_dates(listing_dates.clone()).await.unwrap();
+        repository.save_listing_dates(listing_dates.clone()).await.unwrap();
+        assert_eq!(listing_dates, repository.fetch_listing_dates().await.unwrap());
+
+        // a dataset in another database of the same server is isolated
+        let mut staging_config = AppConfig::from_file("tests/default.yaml").unwrap().clickhouse;
+        staging_config.tables.database = "candy_ass_staging".to_string();
+        let staging_repository = CandlesticksRepository::from_config(staging_config);
+        staging_repository.init().await.unwrap();
+        let staging_result = staging_repository
+            .fetch_candlesticks_between(vec![OneDay], start_date, start_date + Duration::days(1), false, CandlesticksFilter::default())
+            .await
+            .unwrap();
+        assert!(staging_result.is_empty());
+        assert!(staging_repository.fetch_remaining_windows(7).await.unwrap().is_empty());
+
+        // the symbol-first layout serves both the symbol and the time-slice reads
+        let symbol_history = repository
+            .fetch_symbol_candlesticks(symbol.clone(), OneDay, start_date, start_date + Duration::days(7), false)
+            .await
+            .unwrap();
+        assert_eq!(2, symbol_history.len());
+
+        let mut by_symbol_config = AppConfig::from_file("tests/default.yaml").unwrap().clickhouse;
+        by_symbol_config.layout = StorageLayout::SymbolFirst;
+        let by_symbol_repository = CandlesticksRepository::from_config(by_symbol_config);
+        by_symbol_repository.init().await.unwrap();
+        by_symbol_repository
+            .bulk_insert_candlesticks(vec![vec![BTC_USDT_CANDLESTICK.clone()]])
+            .await
+            .unwrap();
+        by_symbol_repository
+            .bulk_insert_candlesticks(vec![vec![BTC_USDT_CANDLESTICK.clone()]])
+            .await
+            .unwrap();
+        by_symbol_repository.run_optimization().await.unwrap();
+
+        let time_slice = by_symbol_repository
+            .fetch_candlesticks_between(vec![OneDay], start_date, start_date + Duration::days(1), false, CandlesticksFilter::default())
+            .await
+            .unwrap();
+        let symbol_history = by_symbol_repository
+            .fetch_symbol_candlesticks(symbol.clone(), OneDay, start_date, start_date + Duration::days(7), false)
+            .await
+            .unwrap();
+        assert_eq!(1, time_slice.len());
+        assert_eq!(1, symbol_history.len());
+
+        // switching the layout keeps the history once it is copied
+        by_symbol_repository.copy_candlesticks(StorageLayout::TimeFirst).await.unwrap();
+        by_symbol_repository.run_optimization().await.unwrap();
+        let copied_history = by_symbol_repository
+            .fetch_symbol_candlesticks(symbol.clone(), OneDay, start_date, start_date + Duration::days(7), false)
+            .await
+            .unwrap();
+        assert_eq!(2, copied_history.len());
     }
 }
@@ -1,4 +1,5 @@
 pub mod actors;
+pub mod supervision;
 
 pub struct SymbolsFetcherWorker {}
 
@@ -2,13 +2,16 @@ pub mod commands;
 pub mod errors;
 pub mod queries;
 
-pub use self::queries::GetReceiver;
+pub use self::queries::{GetLifecycleReceiver, GetReceiver, GetStatusReceiver};
 use crate::application::actors::symbols_fetcher_actor::commands::Command::Refresh;
+use crate::application::supervision::{LifecycleEvent, RestartPolicy, Supervision};
 use crate::domain::symbol::Symbols;
 use crate::integrations::http::binance::spot_http_client::ExchangeInfoApi;
+use crate::utils::RetryPolicy;
 use actix::{Actor, AsyncContext, Context};
 use std::sync::Arc;
 use std::time::Duration;
+use time::OffsetDateTime;
 use tokio::sync::watch;
 use tokio::sync::watch::Sender;
 use tracing::{debug, info};
@@ -21,24 +24,55 @@ pub enum RefreshPolicy {
     Periodic(Duration),
 }
 
+/// Health of the published symbols snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolsStatus {
+    /// No refresh has completed yet.
+    Pending,
+    /// The last refresh succeeded.
+    Fresh,
+    /// Refreshes are failing since `since`, the last good snapshot is still published.
+    Stale { since: OffsetDateTime, last_error: String },
+    /// Refresh failed and there is no snapshot to fall back to.
+    Failed { last_error: String },
+}
+
 pub struct SymbolsFetcherActor {
     refresh_policy: RefreshPolicy,
+    retry_policy: RetryPolicy,
     binance_client: Arc<dyn ExchangeInfoApi + Send + Sync>,
 
     sender: Sender<Option<Arc<Symbols>>>,
     _receiver: Receiver<Option<Arc<Symbols>>>,
+    status_sender: Sender<SymbolsStatus>,
+    /// A crashed refresh is retried, the last snapshot stays published meanwhile
+    supervision: Supervision,
 }
 
 impl SymbolsFetcherActor {
     pub fn new(refresh_policy: RefreshPolicy, binance_client: Arc<dyn ExchangeInfoApi + Send + Sync>) -> Self {
         let (sender, _receiver) = watch::channel::<Option<Arc<Symbols>>>(None);
+        let (status_sender, _) = watch::channel(SymbolsStatus::Pending);
         Self {
             refresh_policy,
+            retry_policy: RetryPolicy::default(),
             sender,
             binance_client,
             _receiver,
+            status_sender,
+            supervision: Supervision::new("SymbolsFetcherActor"),
         }
     }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.supervision.set_policy(restart_policy);
+        self
+    }
 }
 
 impl Actor for SymbolsFetcherActor {
@@ -65,5 +99,6 @@ impl Actor for SymbolsFetcherActor {
 
     fn stopped(&mut self, _: &mut Self::Context) {
         info!("[SymbolsFetcherActor] is stopped");
+        self.supervision.publish(LifecycleEvent::Stopped { actor: "SymbolsFetcherActor" });
     }
 }
@@ -1,11 +1,16 @@
-use crate::application::actors::symbols_fetcher_actor::SymbolsFetcherActor;
 use crate::application::actors::symbols_fetcher_actor::commands::Command::{Refresh, Shutdown};
 use crate::application::actors::symbols_fetcher_actor::errors::FailedToFetchSymbolsError;
+use crate::application::actors::symbols_fetcher_actor::{SymbolsFetcherActor, SymbolsStatus};
+use crate::application::supervision::{catch_panic, panic_message};
 use crate::domain::symbol::Symbols;
-use actix::{ActorContext, AsyncContext, Handler, Message, ResponseFuture, WrapFuture};
+use crate::integrations::http::binance::spot_http_client::ExchangeInfoApi;
+use actix::{ActorContext, ActorFutureExt, AsyncContext, Handler, Message, ResponseFuture, WrapFuture};
 use futures_util::{FutureExt, TryFutureExt};
 use std::sync::Arc;
-use tracing::info;
+use time::OffsetDateTime;
+use tokio::sync::watch::Sender;
+use tokio_retry::Retry;
+use tracing::{error, info, warn};
 
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -20,8 +25,41 @@ impl Handler<Command> for SymbolsFetcherActor {
     fn handle(&mut self, msg: Command, ctx: &mut Self::Context) -> Self::Result {
         match msg {
             Refresh => {
-                let address = ctx.address().clone();
-                ctx.spawn(async move { address.do_send(RefreshAndGet) }.into_actor(self));
+                let binance_client = self.binance_client.clone();
+                let sender = self.sender.clone();
+                let status_sender = self.status_sender.clone();
+                let strategy = self.retry_policy.strategy();
+
+                let refresh = async move {
+                    let attempt = || {
+                        refresh_symbols(binance_client.clone(), sender.clone()).inspect_err(|err| {
+                            warn!("[SymbolsFetcherActor] refresh attempt failed: {}", err);
+                            publish_failure(&sender, &status_sender, err, false);
+                        })
+                    };
+
+                    match Retry::start(strategy, attempt).await {
+                        Ok(_) => publish_success(&status_sender),
+                        Err(err) => {
+                            error!("[SymbolsFetcherActor] refresh failed, retries are exhausted: {}", err);
+                            publish_failure(&sender, &status_sender, &err, true);
+                        }
+                    }
+                };
+
+                // the snapshot lives in the actor, a crashed refresh leaves it as it was
+                ctx.spawn(catch_panic(refresh).into_actor(self).map(|result, act, ctx| match result {
+                    Ok(_) => act.supervision.finished(&()),
+                    Err(panic) => {
+                        let err = FailedToFetchSymbolsError::Crashed(panic_message(panic.as_ref()));
+                        match act.supervision.crashed((), panic) {
+                            Some(backoff) => {
+                                ctx.run_later(backoff, |_, ctx| ctx.address().do_send(Refresh));
+                            }
+                            None => publish_failure(&act.sender, &act.status_sender, &err, true),
+                        }
+                    }
+                }));
             }
             Shutdown => {
                 info!("[SymbolsFetcherActor] is completing it's work");
@@ -41,18 +79,49 @@ impl Handler<RefreshAndGet> for SymbolsFetcherActor {
     fn handle(&mut self, _msg: RefreshAndGet, _ctx: &mut Self::Context) -> Self::Result {
         let binance_client = self.binance_client.clone();
         let sender = self.sender.clone();
+        let status_sender = self.status_sender.clone();
 
         async move {
-            binance_client
-                .fetch_binance_exchange_info()
-                .map_ok(|(exchange_info, _)| {
-                    let symbols = exchange_info.to_symbols();
-                    let arc_symbols = Arc::new(symbols);
-                    let _ = sender.send(Some(arc_symbols.clone()));
-                    Ok(arc_symbols)
-                })
-                .await?
+            refresh_symbols(binance_client, sender.clone())
+                .inspect_ok(|_| publish_success(&status_sender))
+                .inspect_err(|err| publish_failure(&sender, &status_sender, err, true))
+                .await
         }
         .boxed()
     }
 }
+
+async fn refresh_symbols(
+    binance_client: Arc<dyn ExchangeInfoApi + Send + Sync>,
+    sender: Sender<Option<Arc<Symbols>>>,
+) -> Result<Arc<Symbols>, FailedToFetchSymbolsError> {
+    let (exchange_info, _) = binance_client.fetch_binance_exchange_info().await?;
+    let arc_symbols = Arc::new(exchange_info.to_symbols());
+    let _ = sender.send(Some(arc_symbols.clone()));
+    Ok(arc_symbols)
+}
+
+fn publish_success(status_sender: &Sender<SymbolsStatus>) {
+    status_sender.send_replace(SymbolsStatus::Fresh);
+}
+
+/// Keeps the last good snapshot untouched: with a snapshot the status turns `Stale`,
+/// without one it turns `Failed` only once there is nothing left to retry.
+fn publish_failure(sender: &Sender<Option<Arc<Symbols>>>, status_sender: &Sender<SymbolsStatus>, err: &FailedToFetchSymbolsError, exhausted: bool) {
+    let has_snapshot = sender.borrow().is_some();
+    let last_error = err.to_string();
+
+    status_sender.send_if_modified(|status| {
+        let next = match status {
+            SymbolsStatus::Stale { since, .. } if has_snapshot => SymbolsStatus::Stale { since: *since, last_error },
+            _ if has_snapshot => SymbolsStatus::Stale {
+                since: OffsetDateTime::now_utc(),
+                last_error,
+            },
+            _ if exhausted => SymbolsStatus::Failed { last_error },
+            _ => return false,
+        };
+        *status = next;
+        true
+    });
+}
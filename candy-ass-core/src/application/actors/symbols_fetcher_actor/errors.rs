@@ -5,4 +5,7 @@ use thiserror::Error;
 pub enum FailedToFetchSymbolsError {
     #[error("Request failed: {0}")]
     Transport(#[from] HttpResponseError),
+
+    #[error("Refresh crashed: {0}")]
+    Crashed(String),
 }
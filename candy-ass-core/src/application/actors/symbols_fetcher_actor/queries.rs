@@ -1,7 +1,9 @@
-use crate::application::actors::symbols_fetcher_actor::SymbolsFetcherActor;
+use crate::application::actors::symbols_fetcher_actor::{SymbolsFetcherActor, SymbolsStatus};
+use crate::application::supervision::LifecycleEvent;
 use crate::domain::symbol::Symbols;
 use actix::{Handler, Message, MessageResult};
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use tokio::sync::watch::Receiver;
 
 #[derive(Message)]
@@ -14,3 +16,26 @@ impl Handler<GetReceiver> for SymbolsFetcherActor {
         MessageResult(self.sender.subscribe())
     }
 }
+
+#[derive(Message)]
+#[rtype(result = "Receiver<SymbolsStatus>")]
+pub struct GetStatusReceiver;
+
+impl Handler<GetStatusReceiver> for SymbolsFetcherActor {
+    type Result = MessageResult<GetStatusReceiver>;
+    fn handle(&mut self, _msg: GetStatusReceiver, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.status_sender.subscribe())
+    }
+}
+
+/// Crashes and restarts of the actor from now on
+#[derive(Message)]
+#[rtype(result = "broadcast::Receiver<LifecycleEvent>")]
+pub struct GetLifecycleReceiver;
+
+impl Handler<GetLifecycleReceiver> for SymbolsFetcherActor {
+    type Result = MessageResult<GetLifecycleReceiver>;
+    fn handle(&mut self, _msg: GetLifecycleReceiver, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.supervision.subscribe())
+    }
+}
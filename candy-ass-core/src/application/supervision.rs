@@ -0,0 +1,212 @@
+use futures::FutureExt;
+use futures::future::CatchUnwind;
+use std::any::Any;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+
+/// Lifecycle events kept for slow subscribers, older ones are dropped
+const LIFECYCLE_EVENTS_BUFFER: usize = 64;
+
+/// How a supervised actor recovers from a panic in one of its spawned futures
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// Crashes of a task that are restarted, the count is reset once the task finishes
+    pub max_restarts: usize,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RestartPolicy {
+    pub fn new(max_restarts: usize, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_restarts,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// Crashes are given up right away
+    pub fn never() -> Self {
+        Self::new(0, Duration::ZERO, Duration::ZERO)
+    }
+
+    /// Delay before the given restart (1-based): `initial_backoff`, doubled on every restart and capped by `max_backoff`
+    pub fn backoff(&self, restart: usize) -> Duration {
+        let factor = 2u32.saturating_pow(restart.saturating_sub(1) as u32);
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self::new(3, Duration::from_secs(1), Duration::from_secs(30))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// A spawned future panicked, its work is restored and restarted unless the policy gives up
+    Crashed {
+        actor: &'static str,
+        reason: String,
+    },
+    /// The work of the actor is restarted after `backoff`, `restart` counts the crashes of the task
+    Restarting {
+        actor: &'static str,
+        restart: usize,
+        backoff: Duration,
+    },
+    /// Restarts are exhausted, the crashed work is abandoned
+    GaveUp {
+        actor: &'static str,
+        restarts: usize,
+    },
+    Stopped {
+        actor: &'static str,
+    },
+}
+
+/// Restart bookkeeping of a supervised actor, it lives in the actor state so it survives the restarts.
+/// Every task, e.g. a job, has its own restart budget, actors with a single task use `()`.
+#[derive(Debug, Clone)]
+pub struct Supervision<K = ()> {
+    actor: &'static str,
+    policy: RestartPolicy,
+    restarts: HashMap<K, usize>,
+    events: broadcast::Sender<LifecycleEvent>,
+}
+
+impl<K: Eq + Hash> Supervision<K> {
+    pub fn new(actor: &'static str) -> Self {
+        Self {
+            actor,
+            policy: RestartPolicy::default(),
+            restarts: HashMap::new(),
+            events: broadcast::Sender::new(LIFECYCLE_EVENTS_BUFFER),
+        }
+    }
+
+    pub fn set_policy(&mut self, policy: RestartPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.events.subscribe()
+    }
+
+    /// Nobody may be listening, events are best effort
+    pub fn publish(&self, event: LifecycleEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Marks the end of a task that didn't crash, its restart budget is whole again
+    pub fn finished(&mut self, task: &K) {
+        self.restarts.remove(task);
+    }
+
+    /// Records the crash of the task and returns the backoff of its restart, `None` once the policy gives up
+    pub fn crashed(&mut self, task: K, panic: Box<dyn Any + Send>) -> Option<Duration> {
+        let reason = panic_message(panic.as_ref());
+        error!("[{}] crashed: {}", self.actor, reason);
+        self.publish(LifecycleEvent::Crashed { actor: self.actor, reason });
+
+        let restarts = self.restarts.entry(task).or_default();
+        if *restarts >= self.policy.max_restarts {
+            let restarts = std::mem::take(restarts);
+            error!("[{}] gave up after {} restarts", self.actor, restarts);
+            self.publish(LifecycleEvent::GaveUp { actor: self.actor, restarts });
+            return None;
+        }
+
+        *restarts += 1;
+        let restart = *restarts;
+        let backoff = self.policy.backoff(restart);
+        warn!("[{}] is restarting in {:?} ({}/{})", self.actor, backoff, restart, self.policy.max_restarts);
+        self.publish(LifecycleEvent::Restarting {
+            actor: self.actor,
+            restart,
+            backoff,
+        });
+        Some(backoff)
+    }
+}
+
+/// Turns a panic of the future into an `Err`, so the actor that spawned it keeps running and can restart it
+pub fn catch_panic<F: Future>(future: F) -> CatchUnwind<AssertUnwindSafe<F>> {
+    AssertUnwindSafe(future).catch_unwind()
+}
+
+pub fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_backoff() {
+        let policy = RestartPolicy::new(5, Duration::from_secs(1), Duration::from_secs(5));
+        assert_eq!(Duration::from_secs(1), policy.backoff(1));
+        assert_eq!(Duration::from_secs(4), policy.backoff(3));
+        assert_eq!(Duration::from_secs(5), policy.backoff(4));
+    }
+
+    #[test]
+    fn test_supervision_gives_up_after_max_restarts() {
+        let mut supervision = Supervision::new("TestActor");
+        supervision.set_policy(RestartPolicy::new(1, Duration::from_millis(10), Duration::from_secs(1)));
+        let mut events = supervision.subscribe();
+
+        assert_eq!(Some(Duration::from_millis(10)), supervision.crashed((), Box::new("boom")));
+        assert_eq!(None, supervision.crashed((), Box::new("boom".to_string())));
+
+        let crashed = LifecycleEvent::Crashed {
+            actor: "TestActor",
+            reason: "boom".to_string(),
+        };
+        assert_eq!(crashed, events.try_recv().unwrap());
+        assert!(matches!(events.try_recv().unwrap(), LifecycleEvent::Restarting { restart: 1, .. }));
+        assert_eq!(crashed, events.try_recv().unwrap());
+        assert_eq!(
+            LifecycleEvent::GaveUp {
+                actor: "TestActor",
+                restarts: 1
+            },
+            events.try_recv().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_restart_budget_is_kept_per_task() {
+        // given: one restart per task
+        let mut supervision = Supervision::<u64>::new("TestActor");
+        supervision.set_policy(RestartPolicy::new(1, Duration::from_millis(10), Duration::from_secs(1)));
+
+        // when: another task crashes after the first one used its restart
+        assert!(supervision.crashed(1, Box::new("boom")).is_some());
+        let other_task = supervision.crashed(2, Box::new("boom"));
+        let exhausted = supervision.crashed(1, Box::new("boom"));
+
+        // then: a finished task starts over with a whole budget
+        assert_eq!(Some(Duration::from_millis(10)), other_task);
+        assert_eq!(None, exhausted);
+        supervision.finished(&2);
+        assert!(supervision.crashed(2, Box::new("boom")).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_catch_panic() {
+        let result = catch_panic(async { panic!("boom") }).await;
+        assert_eq!("boom", panic_message(result.unwrap_err().as_ref()));
+        assert_eq!(Ok(1), catch_panic(async { 1 }).await.map_err(|_| ()));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod candlestick;
+pub mod candlesticks_window;
 pub mod exchange_type;
 pub mod symbol;
 pub mod timeframe;
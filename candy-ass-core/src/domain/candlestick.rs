@@ -17,4 +17,18 @@ pub struct Candlestick {
     pub low_price: f64,
     pub high_price: f64,
     pub volume: f64,
+    /// `false` for the still-open candlestick of the current period, its prices and volume are not final yet
+    #[serde(default = "closed")]
+    pub is_closed: bool,
+}
+
+fn closed() -> bool {
+    true
+}
+
+impl Candlestick {
+    /// Whether the candlestick period is over at `time`, e.g. the exchange time of the response it came with
+    pub fn is_closed_at(&self, time: OffsetDateTime) -> bool {
+        self.close_time < time
+    }
 }
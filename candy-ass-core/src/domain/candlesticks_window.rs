@@ -0,0 +1,30 @@
+use crate::domain::symbol::Symbol;
+use crate::domain::timeframe::Timeframe;
+use std::fmt::Display;
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+/// Range of candlesticks of one symbol and timeframe, bounded by `open_time` (both ends inclusive).
+/// An open `end` means "up to the latest candlestick".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CandlesticksWindow {
+    pub symbol: Arc<Symbol>,
+    pub timeframe: Timeframe,
+    pub start: OffsetDateTime,
+    pub end: Option<OffsetDateTime>,
+}
+
+impl CandlesticksWindow {
+    pub fn new(symbol: Arc<Symbol>, timeframe: Timeframe, start: OffsetDateTime, end: Option<OffsetDateTime>) -> Self {
+        Self { symbol, timeframe, start, end }
+    }
+}
+
+impl Display for CandlesticksWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{} {} [{}, {}]", self.symbol.short_name(), self.timeframe, self.start, end),
+            None => write!(f, "{} {} [{}, ..]", self.symbol.short_name(), self.timeframe, self.start),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use strum_macros::{AsRefStr, EnumIter, EnumString};
+use time::Duration;
 
 #[derive(Serialize, Deserialize, Hash, PartialEq, Eq, Debug, Clone, EnumString, AsRefStr, EnumIter)]
 pub enum Timeframe {
@@ -45,6 +46,27 @@ pub enum Timeframe {
     OneDay,
 }
 
+impl Timeframe {
+    /// Distance between `open_time` of two consecutive candlesticks
+    pub fn duration(&self) -> Duration {
+        match self {
+            Timeframe::OneMinute => Duration::minutes(1),
+            Timeframe::ThreeMinutes => Duration::minutes(3),
+            Timeframe::FiveMinutes => Duration::minutes(5),
+            Timeframe::FifteenMinutes => Duration::minutes(15),
+            Timeframe::ThirtyMinutes => Duration::minutes(30),
+            Timeframe::OneHour => Duration::hours(1),
+            Timeframe::TwoHours => Duration::hours(2),
+            Timeframe::ThreeHours => Duration::hours(3),
+            Timeframe::FourHours => Duration::hours(4),
+            Timeframe::SixHours => Duration::hours(6),
+            Timeframe::EightHours => Duration::hours(8),
+            Timeframe::TwelveHours => Duration::hours(12),
+            Timeframe::OneDay => Duration::days(1),
+        }
+    }
+}
+
 impl Display for Timeframe {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_ref())
@@ -54,8 +76,9 @@ impl Display for Timeframe {
 #[cfg(test)]
 mod tests {
     use crate::domain::timeframe::Timeframe;
-    use crate::domain::timeframe::Timeframe::{OneMinute, ThreeMinutes};
+    use crate::domain::timeframe::Timeframe::{OneDay, OneMinute, ThreeMinutes};
     use std::str::FromStr;
+    use time::Duration;
 
     #[test]
     fn to_str() {
@@ -83,4 +106,11 @@ mod tests {
         // Then
         assert_eq!(min_1_str, min_1);
     }
+
+    #[test]
+    fn duration() {
+        assert_eq!(Duration::minutes(1), OneMinute.duration());
+        assert_eq!(Duration::minutes(3), ThreeMinutes.duration());
+        assert_eq!(Duration::days(1), OneDay.duration());
+    }
 }
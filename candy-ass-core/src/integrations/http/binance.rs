@@ -2,6 +2,8 @@ pub mod spot_http_client;
 
 pub const BINANCE_SPOT_BASE_URL: &str = "https://api.binance.com/api";
 pub const BINANCE_RATE_LIMIT: usize = 25;
+/// Request weight of a klines request, whatever its `limit`
+pub const BINANCE_KLINES_WEIGHT: u32 = 2;
 
 pub const BINANCE_HEADER_USED_WEIGHT: &str = "x-mbx-used-weight";
 pub const BINANCE_HEADER_USED_WEIGHT_1M: &str = "x-mbx-used-weight-1m";
@@ -8,9 +8,10 @@ use crate::integrations::http::utils_parser::{parse_f64, parse_u64};
 use crate::utils::OffsetDateTimeExt;
 use futures::future::BoxFuture;
 use futures::{FutureExt, TryFutureExt, future};
-use reqwest::header::HeaderMap;
+use reqwest::header::{DATE, HeaderMap};
 use std::sync::Arc;
 use time::OffsetDateTime;
+use time::format_description::well_known::Rfc2822;
 
 #[derive(Debug)]
 pub struct KlineResponse {
@@ -42,6 +43,7 @@ impl KlineResponse {
             low_price: self.low,
             high_price: self.high,
             volume: self.volume,
+            is_closed: true,
         }
     }
 }
@@ -54,7 +56,7 @@ impl KlinesApi for BinanceSpotClient {
         limit: u16,
         start_time: Option<OffsetDateTime>,
         end_time: Option<OffsetDateTime>,
-    ) -> BoxFuture<Result<(Vec<Candlestick>, HeaderMap), HttpResponseError>> {
+    ) -> BoxFuture<'_, Result<(Vec<Candlestick>, HeaderMap), HttpResponseError>> {
         let url = UrlBuilder::new(self.base_url, "/api/v3/klines")
             .with_param("symbol", symbol.short_name())
             .with_param("interval", timeframe.as_ref())
@@ -68,9 +70,11 @@ impl KlinesApi for BinanceSpotClient {
             .send()
             .parse_json_or_error::<Vec<Vec<serde_json::Value>>>()
             .and_then(move |(raw, headers)| {
+                let exchange_time = exchange_time(&headers);
                 match raw
                     .into_iter()
                     .map(|json_arr| Candlestick::try_from_json_array(json_arr, symbol.clone(), timeframe.clone()))
+                    .map(|candlestick| candlestick.map(|candlestick| mark_closed(candlestick, exchange_time)))
                     .collect::<Result<Vec<Candlestick>, _>>()
                 {
                     Ok(candlesticks) => future::ready(Ok((candlesticks, headers))),
@@ -81,6 +85,23 @@ impl KlinesApi for BinanceSpotClient {
     }
 }
 
+/// Server time of the response, the local clock is used when the `Date` header is missing or malformed
+fn exchange_time(headers: &HeaderMap) -> OffsetDateTime {
+    headers
+        .get(DATE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| OffsetDateTime::parse(value, &Rfc2822).ok())
+        .unwrap_or_else(OffsetDateTime::now_utc)
+}
+
+/// Klines carry no closed flag, the last one of the latest page is usually the still-open candlestick
+fn mark_closed(candlestick: Candlestick, exchange_time: OffsetDateTime) -> Candlestick {
+    Candlestick {
+        is_closed: candlestick.is_closed_at(exchange_time),
+        ..candlestick
+    }
+}
+
 pub trait CandlestickTryFromJsonArray {
     fn try_from_json_array(raw: Vec<serde_json::Value>, symbol: Arc<Symbol>, timeframe: Timeframe) -> Result<Self, HttpResponseError>
     where
@@ -107,6 +128,7 @@ impl CandlestickTryFromJsonArray for Candlestick {
             low_price: parse_f64(&raw[3], "low")?,
             close_price: parse_f64(&raw[4], "close")?,
             volume: parse_f64(&raw[5], "volume")?,
+            is_closed: true,
         })
     }
 }
@@ -199,4 +221,24 @@ mod tests {
         assert_eq!(candlestick.open_time, OffsetDateTime::from_unix_timestamp_millis(1_682_544_000_000).unwrap());
         assert_eq!(candlestick.close_time, OffsetDateTime::from_unix_timestamp_millis(1_682_547_800_000).unwrap());
     }
+
+    #[test]
+    fn still_open_candlestick_test() {
+        let mut headers = HeaderMap::new();
+        headers.insert(DATE, "Sun, 29 Jun 2025 17:14:00 GMT".parse().unwrap());
+        let exchange_time = exchange_time(&headers);
+
+        let closed = Candlestick {
+            close_time: exchange_time - time::Duration::milliseconds(1),
+            ..crate::mocks::fixtures::BTC_USDT_CANDLESTICK.clone()
+        };
+        let open = Candlestick {
+            close_time: exchange_time + time::Duration::seconds(59),
+            ..closed.clone()
+        };
+
+        assert_eq!(1_751_217_240, exchange_time.unix_timestamp());
+        assert!(mark_closed(closed, exchange_time).is_closed);
+        assert!(!mark_closed(open, exchange_time).is_closed);
+    }
 }
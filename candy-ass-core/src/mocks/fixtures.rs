@@ -21,6 +21,7 @@ pub static BTC_USDT_CANDLESTICK: LazyLock<Candlestick> = LazyLock::new(|| {
         high_price: 101_000.001,
         low_price: 99_999.999,
         volume: 1_111.1,
+        is_closed: true,
     }
 });
 
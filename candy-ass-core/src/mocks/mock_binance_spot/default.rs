@@ -1,5 +1,6 @@
 use crate::domain::candlestick::Candlestick;
 use crate::domain::symbol::Symbol;
+use crate::domain::timeframe::Timeframe;
 use crate::integrations::http::HttpResponseError;
 use crate::integrations::http::binance::spot_http_client::exchange_info_api::{ExchangeInfoResponse, ExchangeInfoSymbols};
 use crate::mocks::fixtures::mock_candlesticks;
@@ -7,6 +8,7 @@ use crate::mocks::mock_binance_spot::{HEADER_MAP, MockBinanceSpotClient};
 use axum::http::HeaderMap;
 use std::sync::{Arc, LazyLock};
 use std::time::Duration;
+use time::OffsetDateTime;
 use tokio::time::sleep;
 
 pub static DEFAULT_BINANCE_SPOT_CLIENT: LazyLock<Arc<MockBinanceSpotClient>> = LazyLock::new(|| {
@@ -19,7 +21,9 @@ pub static DEFAULT_BINANCE_SPOT_CLIENT: LazyLock<Arc<MockBinanceSpotClient>> = L
     });
     binance_spot_client
         .expect_fetch_candlesticks()
-        .returning(move |symbol, _, _, _, _| Box::pin(async move { fake_candlesticks(symbol).await }));
+        .returning(move |symbol, timeframe, limit, start_time, end_time| {
+            Box::pin(async move { fake_candlesticks(symbol, timeframe, limit, start_time, end_time).await })
+        });
     Arc::new(binance_spot_client)
 });
 
@@ -42,11 +46,27 @@ pub fn fake_exchange_info_response() -> Result<(ExchangeInfoResponse, HeaderMap)
     Ok((result, HEADER_MAP.clone()))
 }
 
-async fn fake_candlesticks(symbol: Arc<Symbol>) -> Result<(Vec<Candlestick>, HeaderMap), HttpResponseError> {
-    let result = mock_candlesticks(symbol).await;
+/// Mimics the klines endpoint: candlesticks within `[start_time, end_time]`, at most `limit` of them.
+/// Fixtures are relabeled with the requested timeframe, their cadence stays the same.
+pub async fn fake_candlesticks(
+    symbol: Arc<Symbol>,
+    timeframe: Timeframe,
+    limit: u16,
+    start_time: Option<OffsetDateTime>,
+    end_time: Option<OffsetDateTime>,
+) -> Result<(Vec<Candlestick>, HeaderMap), HttpResponseError> {
+    let result = mock_candlesticks(symbol).await.unwrap_or_default();
 
-    match result {
-        Some(result) => Ok((result, HEADER_MAP.clone())),
-        None => Ok((vec![], HEADER_MAP.clone())),
-    }
+    let result = result
+        .into_iter()
+        .filter(|candlestick| start_time.is_none_or(|start_time| candlestick.open_time >= start_time))
+        .filter(|candlestick| end_time.is_none_or(|end_time| candlestick.open_time <= end_time))
+        .take(limit as usize)
+        .map(|candlestick| Candlestick {
+            timeframe: timeframe.clone(),
+            ..candlestick
+        })
+        .collect();
+
+    Ok((result, HEADER_MAP.clone()))
 }
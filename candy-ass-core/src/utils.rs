@@ -1,5 +1,7 @@
+use std::time::Duration;
 use time::OffsetDateTime;
 use time::error::ComponentRange;
+use tokio_retry::strategy::ExponentialBackoff;
 
 pub trait OffsetDateTimeExt {
     fn from_unix_timestamp_millis(millis: i64) -> Result<OffsetDateTime, ComponentRange>;
@@ -16,9 +18,43 @@ impl OffsetDateTimeExt for OffsetDateTime {
     }
 }
 
+/// Bounded exponential backoff for retrying flaky remote calls.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: usize,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: usize, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_retries,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// Delays between attempts: `initial_backoff`, doubled on every retry and capped by `max_backoff`.
+    pub fn strategy(&self) -> impl Iterator<Item = Duration> + use<> {
+        let factor = (self.initial_backoff.as_millis() as u64 / 2).max(1);
+        ExponentialBackoff::from_millis(2)
+            .factor(factor)
+            .max_delay(self.max_backoff)
+            .take(self.max_retries)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(5, Duration::from_millis(500), Duration::from_secs(30))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
     use time::OffsetDateTime;
 
     #[test]
@@ -49,4 +85,19 @@ mod tests {
         let result = OffsetDateTime::from_unix_timestamp_millis(millis);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_retry_policy_strategy() {
+        let policy = RetryPolicy::new(4, Duration::from_millis(100), Duration::from_millis(300));
+        let delays = policy.strategy().collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(300),
+                Duration::from_millis(300)
+            ],
+            delays
+        );
+    }
 }
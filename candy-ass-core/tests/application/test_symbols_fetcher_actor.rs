@@ -2,19 +2,46 @@
 mod tests {
     use actix::Actor;
     use candy_ass_core::application::actors::symbols_fetcher_actor::RefreshPolicy::Periodic;
+    use candy_ass_core::application::actors::symbols_fetcher_actor::commands::Command::Refresh;
     use candy_ass_core::application::actors::symbols_fetcher_actor::commands::Command::Shutdown;
     use candy_ass_core::application::actors::symbols_fetcher_actor::commands::RefreshAndGet;
     use candy_ass_core::application::actors::symbols_fetcher_actor::errors::FailedToFetchSymbolsError;
-    use candy_ass_core::application::actors::symbols_fetcher_actor::{GetReceiver, RefreshPolicy, SymbolsFetcherActor};
+    use candy_ass_core::application::actors::symbols_fetcher_actor::{
+        GetLifecycleReceiver, GetReceiver, GetStatusReceiver, RefreshPolicy, SymbolsFetcherActor, SymbolsStatus,
+    };
+    use candy_ass_core::application::supervision::{LifecycleEvent, RestartPolicy};
     use candy_ass_core::domain::symbol::Symbols;
-    use candy_ass_core::mocks::mock_binance_spot::broken::BROKEN_BINANCE_SPOT_CLIENT;
-    use candy_ass_core::mocks::mock_binance_spot::default::DEFAULT_BINANCE_SPOT_CLIENT;
+    use candy_ass_core::mocks::mock_binance_spot::MockBinanceSpotClient;
+    use candy_ass_core::mocks::mock_binance_spot::broken::{BROKEN_BINANCE_SPOT_CLIENT, fake_http_error};
+    use candy_ass_core::mocks::mock_binance_spot::default::{DEFAULT_BINANCE_SPOT_CLIENT, fake_exchange_info_response};
+    use candy_ass_core::utils::RetryPolicy;
     use futures_util::StreamExt;
     use futures_util::future::ready;
     use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::time::Duration;
     use tokio_stream::wrappers::WatchStream;
 
+    fn fast_retry_policy() -> RetryPolicy {
+        RetryPolicy::new(2, Duration::from_millis(1), Duration::from_millis(2))
+    }
+
+    /// Succeeds on the calls listed in `successful_calls`, fails on every other one.
+    fn flaky_binance_client(successful_calls: &'static [usize]) -> Arc<MockBinanceSpotClient> {
+        let calls = AtomicUsize::new(0);
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client.expect_fetch_binance_exchange_info().returning(move || {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                match successful_calls.contains(&call) {
+                    true => fake_exchange_info_response(),
+                    false => Err(fake_http_error()),
+                }
+            })
+        });
+        Arc::new(binance_client)
+    }
+
     #[actix::test]
     async fn test_refresh_triggers_logic() {
         // Given
@@ -88,4 +115,106 @@ mod tests {
         // Then
         assert!(matches!(err, FailedToFetchSymbolsError::Transport(_)));
     }
+
+    #[actix::test]
+    async fn test_one_shot_retries_after_failure() {
+        // Given
+        let binance_client = flaky_binance_client(&[1]);
+        let symbols_fetcher_actor = SymbolsFetcherActor::new(RefreshPolicy::OneShot, binance_client)
+            .with_retry_policy(fast_retry_policy())
+            .start();
+
+        // When
+        let receiver = symbols_fetcher_actor.send(GetReceiver).await.unwrap();
+        let mut status_receiver = symbols_fetcher_actor.send(GetStatusReceiver).await.unwrap();
+
+        // Then
+        let state = WatchStream::new(receiver).filter_map(ready).next().await.unwrap();
+        let status = status_receiver.wait_for(|status| *status != SymbolsStatus::Pending).await.unwrap().clone();
+
+        symbols_fetcher_actor.send(Shutdown).await.unwrap();
+        assert_eq!(2, state.len());
+        assert_eq!(SymbolsStatus::Fresh, status);
+    }
+
+    #[actix::test]
+    async fn test_one_shot_reports_failed_status() {
+        // Given
+        let binance_client = BROKEN_BINANCE_SPOT_CLIENT.clone();
+        let symbols_fetcher_actor = SymbolsFetcherActor::new(RefreshPolicy::OneShot, binance_client)
+            .with_retry_policy(fast_retry_policy())
+            .start();
+
+        // When
+        let mut status_receiver = symbols_fetcher_actor.send(GetStatusReceiver).await.unwrap();
+        let status = status_receiver.wait_for(|status| *status != SymbolsStatus::Pending).await.unwrap().clone();
+
+        // Then
+        let receiver = symbols_fetcher_actor.send(GetReceiver).await.unwrap();
+        let state = receiver.borrow().clone();
+
+        symbols_fetcher_actor.send(Shutdown).await.unwrap();
+        assert_eq!(None, state);
+        assert!(matches!(status, SymbolsStatus::Failed { last_error } if last_error.contains("404")));
+    }
+
+    #[actix::test]
+    async fn test_periodic_keeps_last_snapshot_when_stale() {
+        // Given
+        let binance_client = flaky_binance_client(&[0]);
+        let symbols_fetcher_actor = SymbolsFetcherActor::new(Periodic(Duration::from_millis(20)), binance_client)
+            .with_retry_policy(fast_retry_policy())
+            .start();
+
+        // When
+        let mut status_receiver = symbols_fetcher_actor.send(GetStatusReceiver).await.unwrap();
+        status_receiver.wait_for(|status| *status == SymbolsStatus::Fresh).await.unwrap();
+        let status = status_receiver
+            .wait_for(|status| matches!(status, SymbolsStatus::Stale { .. }))
+            .await
+            .unwrap()
+            .clone();
+
+        // Then
+        let receiver = symbols_fetcher_actor.send(GetReceiver).await.unwrap();
+        let state = receiver.borrow().clone().unwrap();
+
+        symbols_fetcher_actor.send(Shutdown).await.unwrap();
+        assert_eq!(2, state.len());
+        assert!(matches!(status, SymbolsStatus::Stale { .. }));
+    }
+
+    #[actix::test]
+    async fn test_crashed_refresh_is_restarted() {
+        // Given: the first refresh panics
+        let calls = AtomicUsize::new(0);
+        let mut binance_client = MockBinanceSpotClient::new();
+        binance_client.expect_fetch_binance_exchange_info().returning(move || {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                if call == 0 {
+                    panic!("exchange info exploded");
+                }
+                fake_exchange_info_response()
+            })
+        });
+        let restart_policy = RestartPolicy::new(1, Duration::from_millis(1), Duration::from_millis(1));
+        let symbols_fetcher_actor = SymbolsFetcherActor::new(RefreshPolicy::Lazy, Arc::new(binance_client))
+            .with_restart_policy(restart_policy)
+            .start();
+        let mut events = symbols_fetcher_actor.send(GetLifecycleReceiver).await.unwrap();
+        let receiver = symbols_fetcher_actor.send(GetReceiver).await.unwrap();
+
+        // When
+        symbols_fetcher_actor.send(Refresh).await.unwrap();
+
+        // Then
+        let state = WatchStream::new(receiver).filter_map(ready).next().await.unwrap();
+        assert_eq!(2, state.len());
+        assert!(matches!(events.recv().await.unwrap(), LifecycleEvent::Crashed { reason, .. } if reason == "exchange info exploded"));
+        assert!(matches!(events.recv().await.unwrap(), LifecycleEvent::Restarting { restart: 1, .. }));
+
+        symbols_fetcher_actor.send(Shutdown).await.unwrap();
+        assert_eq!(LifecycleEvent::Stopped { actor: "SymbolsFetcherActor" }, events.recv().await.unwrap());
+    }
 }